@@ -0,0 +1,39 @@
+use imperator_save::SaveHeaderKind;
+use std::fmt::Write;
+
+#[path = "../../tests/synthetic/mod.rs"]
+#[allow(dead_code)]
+pub mod synthetic;
+
+/// A compressed save of the given kind with a gamestate of roughly the
+/// requested size. Binary saves are encoded with [`synthetic::TOKENS`] so
+/// that they can be resolved without the real tokens.
+pub fn synthetic_save(kind: SaveHeaderKind, gamestate_len: usize) -> Vec<u8> {
+    let mut gamestate = String::from("speed=3\ncharacter={\n\tdatabase={\n");
+    let mut id = 0;
+    while gamestate.len() < gamestate_len / 2 {
+        writeln!(
+            gamestate,
+            "\t\t{}={{ country={} culture=roman religion=roman_pantheon birth_date=420.5.1 }}",
+            id,
+            id % 100
+        )
+        .unwrap();
+        id += 1;
+    }
+
+    gamestate.push_str("\t}\n}\ncountry={\n\tdatabase={\n");
+    let mut id = 0;
+    while gamestate.len() < gamestate_len {
+        writeln!(
+            gamestate,
+            "\t\t{}={{ tag=\"ROM\" currency_data={{ gold=105.5 manpower=3.25 stability=50 }} }}",
+            id
+        )
+        .unwrap();
+        id += 1;
+    }
+    gamestate.push_str("\t}\n}\n");
+
+    synthetic::zip_with(kind, &gamestate)
+}
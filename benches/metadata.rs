@@ -0,0 +1,34 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use imperator_save::{
+    models::Save, DeserializeImperator, ImperatorFile, ImperatorMetadata, SaveHeaderKind,
+};
+
+mod common;
+
+fn metadata_benchmark(c: &mut Criterion) {
+    let resolver = &*common::synthetic::TOKENS;
+    let mut group = c.benchmark_group("metadata");
+    for (encoding, kind) in [
+        ("text", SaveHeaderKind::UnifiedText),
+        ("binary", SaveHeaderKind::UnifiedBinary),
+    ] {
+        for size in [1 << 20, 16 << 20] {
+            let save = common::synthetic_save(kind, size);
+            let file = ImperatorFile::from_slice(&save).unwrap();
+            let param = format!("{}/{}", encoding, size);
+            group.bench_with_input(BenchmarkId::new("metadata", &param), &file, |b, file| {
+                b.iter(|| file.metadata(resolver).unwrap())
+            });
+            group.bench_with_input(BenchmarkId::new("full-save", &param), &file, |b, file| {
+                b.iter(|| {
+                    let save: Save = (&*file).deserialize(resolver).unwrap();
+                    save
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, metadata_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use imperator_save::{
+    models::Save, DeserializeImperator, ImperatorErrorKind, ImperatorFile, ImperatorMelt,
+    ImperatorMetadata, MeltOptions, SaveHeader, SaveHeaderKind, TokenTable,
+};
+use jomini::TextTape;
+use std::io::Read;
+
+mod common;
+
+#[path = "../tests/utils.rs"]
+#[allow(dead_code)]
+mod utils;
+
+const TEXT_SAMPLES: &[&str] = &["debug-save.rome", "non-ascii.rome"];
+const BINARY_SAMPLES: &[&str] = &["observer1.5.rome", "Oponia.rome"];
+
+/// The sample saves that are available, and the tokens to resolve them
+/// with, along with synthetic text and binary saves so that the suite has
+/// something to measure when offline. Binary samples are only included when
+/// a token file is found.
+fn samples(tokens: &TokenTable) -> Vec<(String, Vec<u8>, &TokenTable)> {
+    let size = 16 << 20;
+    let mut result = vec![
+        (
+            String::from("synthetic-text"),
+            common::synthetic_save(SaveHeaderKind::UnifiedText, size),
+            &*common::synthetic::TOKENS,
+        ),
+        (
+            String::from("synthetic-binary"),
+            common::synthetic_save(SaveHeaderKind::UnifiedBinary, size),
+            &*common::synthetic::TOKENS,
+        ),
+    ];
+
+    let binary = if tokens.is_empty() {
+        eprintln!("no token file found, skipping binary samples");
+        &[][..]
+    } else {
+        BINARY_SAMPLES
+    };
+
+    for name in TEXT_SAMPLES.iter().chain(binary) {
+        match utils::try_request_file(name) {
+            Ok(mut file) => {
+                let mut data = Vec::new();
+                file.read_to_end(&mut data).unwrap();
+                result.push((String::from(*name), data, tokens));
+            }
+            Err(e) => eprintln!("skipping {}: {}", name, e),
+        }
+    }
+
+    result
+}
+
+fn saves_benchmark(c: &mut Criterion) {
+    let tokens = match TokenTable::from_env() {
+        Ok(tokens) => tokens,
+        Err(e) if matches!(e.kind(), ImperatorErrorKind::TokensNotFound { .. }) => {
+            TokenTable::new()
+        }
+        Err(e) => panic!("{}", e),
+    };
+
+    let mut group = c.benchmark_group("saves");
+    group.sample_size(10);
+    for (name, data, tokens) in samples(&tokens) {
+        let file = ImperatorFile::from_slice(&data).unwrap();
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("header", &name), &data, |b, data| {
+            b.iter(|| SaveHeader::from_slice(data).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("envelope", &name), &data, |b, data| {
+            b.iter(|| ImperatorFile::from_slice(data).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("metadata", &name), &file, |b, file| {
+            b.iter(|| file.metadata(tokens).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("melt", &name), &file, |b, file| {
+            b.iter(|| {
+                (&*file)
+                    .melt(MeltOptions::new(), tokens, std::io::sink())
+                    .unwrap()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("deserialize", &name), &file, |b, file| {
+            b.iter(|| {
+                let save: Save = (&*file).deserialize(tokens).unwrap();
+                save
+            })
+        });
+
+        // Measure only the conversion of the melted save to JSON
+        let mut melted = Vec::new();
+        (&file)
+            .melt(MeltOptions::new(), tokens, &mut melted)
+            .unwrap();
+        let header_len = SaveHeader::from_slice(&melted).unwrap().header_len();
+        group.bench_with_input(BenchmarkId::new("json", &name), &melted, |b, melted| {
+            b.iter(|| {
+                let tape = TextTape::from_slice(&melted[header_len..]).unwrap();
+                tape.utf8_reader()
+                    .json()
+                    .to_writer(std::io::sink())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, saves_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+use std::{collections::BTreeMap, env, fmt::Write, fs, path::PathBuf};
+
+include!("src/tokens/parse.rs");
+
+const TOKENS_ENV: &str = "IMPERATOR_TOKENS";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/tokens/parse.rs");
+    if env::var_os("CARGO_FEATURE_EMBEDDED_TOKENS").is_none() {
+        return;
+    }
+
+    println!("cargo:rerun-if-env-changed={}", TOKENS_ENV);
+    let path = match env::var_os(TOKENS_ENV) {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => panic!(
+            "embedded-tokens is enabled but {} is not set to the path of a token file",
+            TOKENS_ENV
+        ),
+    };
+
+    println!("cargo:rerun-if-changed={}", path.display());
+    let data =
+        fs::read(&path).unwrap_or_else(|e| panic!("unable to read {}: {}", path.display(), e));
+    let mut tokens: BTreeMap<u16, String> = BTreeMap::new();
+    if data.starts_with(BINARY_MAGIC) {
+        let parsed = parse_binary(&data).unwrap_or_else(|(offset, msg)| {
+            panic!("{}: offset {}: {}", path.display(), offset, msg)
+        });
+        for (id, token) in parsed {
+            tokens.insert(id, String::from(token));
+        }
+    } else {
+        let data =
+            std::str::from_utf8(&data).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        for (i, line) in data.lines().enumerate() {
+            let parsed = parse_text_line(line)
+                .unwrap_or_else(|msg| panic!("{}:{}: {}", path.display(), i + 1, msg));
+            if let Some((id, token)) = parsed {
+                tokens.insert(id, String::from(token));
+            }
+        }
+    }
+
+    // Token ids are dense, so the table is indexed by id
+    let len = tokens
+        .last_key_value()
+        .map_or(0, |(&id, _)| usize::from(id) + 1);
+    let mut out = String::new();
+    writeln!(out, "static TOKENS: [Option<&str>; {}] = [", len).unwrap();
+    for id in 0..len {
+        match tokens.get(&(id as u16)) {
+            Some(token) => writeln!(out, "    Some({:?}),", token).unwrap(),
+            None => writeln!(out, "    None,").unwrap(),
+        }
+    }
+    writeln!(out, "];").unwrap();
+
+    let dest = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("embedded_tokens.rs");
+    fs::write(dest, out).unwrap();
+}
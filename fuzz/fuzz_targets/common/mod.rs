@@ -0,0 +1,14 @@
+use imperator_save::{ImperatorErrorKind, TokenTable};
+use std::sync::LazyLock;
+
+#[path = "../../../tests/synthetic/mod.rs"]
+#[allow(dead_code)]
+pub mod synthetic;
+
+/// Tokens found through the usual search paths. Without them, binary saves
+/// are still exercised but fail to resolve most fields.
+pub static TOKENS: LazyLock<TokenTable> = LazyLock::new(|| match TokenTable::from_env() {
+    Ok(tokens) => tokens,
+    Err(e) if matches!(e.kind(), ImperatorErrorKind::TokensNotFound { .. }) => TokenTable::new(),
+    Err(e) => panic!("{}", e),
+});
@@ -0,0 +1,39 @@
+#![no_main]
+use imperator_save::{
+    models::{Metadata, Save},
+    DeserializeImperator, ImperatorFile, ImperatorMetadata, ImperatorParallelDeserialize,
+    SaveHeaderKind,
+};
+use libfuzzer_sys::fuzz_target;
+
+mod common;
+use common::{synthetic, TOKENS};
+
+/// Wrap the input in a valid header so that the fuzzer spends its time in
+/// the model deserializers rather than the envelope. The first byte picks
+/// the encoding.
+fn save(data: &[u8]) -> Option<Vec<u8>> {
+    let (&kind, body) = data.split_first()?;
+    let kind = if kind & 1 == 0 {
+        SaveHeaderKind::Text
+    } else {
+        SaveHeaderKind::Binary
+    };
+
+    Some(synthetic::save(kind, 0, body))
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some(data) = save(data) else {
+        return;
+    };
+
+    let Ok(file) = ImperatorFile::from_slice(data) else {
+        return;
+    };
+
+    let _ = file.metadata(&*TOKENS);
+    let _: Result<Metadata, _> = (&file).deserialize(&*TOKENS);
+    let _: Result<Save, _> = (&file).deserialize(&*TOKENS);
+    let _ = (&file).deserialize_databases(&*TOKENS);
+});
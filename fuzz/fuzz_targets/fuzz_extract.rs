@@ -1,29 +1,42 @@
 #![no_main]
-use imperator_save::{file::ImperatorParsedText, BasicTokenResolver};
+use imperator_save::{
+    models::{GameState, Metadata, Save},
+    DeserializeImperator, ImperatorBinaryDeserialization, ImperatorFile, ImperatorMelt,
+    ImperatorMetadata, JominiFileKind, MeltOptions, SaveContentKind, SaveDataKind,
+};
+use jomini::TextTape;
 use libfuzzer_sys::fuzz_target;
-use std::sync::LazyLock;
+use std::io::Read;
 
-static TOKENS: LazyLock<BasicTokenResolver> = LazyLock::new(|| {
-    let file_data = std::fs::read("assets/imperator.txt").unwrap();
-    BasicTokenResolver::from_text_lines(file_data.as_slice()).unwrap()
-});
+mod common;
+use common::TOKENS;
 
 fn run(data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
-    let file = imperator_save::ImperatorFile::from_slice(&data)?;
+    let file = ImperatorFile::from_slice(data)?;
 
-    let mut sink = std::io::sink();
-    let _ = file.melt(imperator_save::MeltOptions::new(), &*TOKENS, &mut sink);
-    let _ = file.parse_save(&*TOKENS);
-    let _ = file.encoding();
+    let _ = (&file).melt(MeltOptions::new(), &*TOKENS, std::io::sink());
+    let _ = file.metadata(&*TOKENS);
+    let _: Result<Save, _> = (&file).deserialize(&*TOKENS);
 
     match file.kind() {
-        imperator_save::file::ImperatorSliceFileKind::Text(x) => {
-            ImperatorParsedText::from_raw(x.get_ref())?
-                .reader()
-                .json()
-                .to_writer(std::io::sink())?;
+        JominiFileKind::Uncompressed(SaveDataKind::Binary(x)) => {
+            let _: Result<Metadata, _> = (&*x).deserializer(&*TOKENS).deserialize();
+        }
+        JominiFileKind::Zip(x) => {
+            if let SaveContentKind::Binary(mut x) = x.gamestate()? {
+                let _: Result<GameState, _> = x.deserializer(&*TOKENS).deserialize();
+            }
         }
-        _ => {}
+        JominiFileKind::Uncompressed(SaveDataKind::Text(_)) => {}
+    }
+
+    if let SaveContentKind::Text(mut x) = file.gamestate()? {
+        let mut data = Vec::new();
+        x.read_to_end(&mut data)?;
+        TextTape::from_slice(&data)?
+            .utf8_reader()
+            .json()
+            .to_writer(std::io::sink())?;
     }
 
     Ok(())
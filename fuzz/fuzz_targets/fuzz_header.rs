@@ -0,0 +1,20 @@
+#![no_main]
+use imperator_save::SaveHeader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(header) = SaveHeader::from_slice(data) else {
+        return;
+    };
+
+    assert!(header.header_len() <= data.len());
+
+    // A parsed header must survive being written and parsed again
+    let mut out = Vec::new();
+    header.write(&mut out).unwrap();
+    let reparsed = SaveHeader::from_slice(&out).unwrap();
+    assert_eq!(reparsed.kind(), header.kind());
+    assert_eq!(reparsed.version(), header.version());
+    assert_eq!(reparsed.metadata_len(), header.metadata_len());
+    assert_eq!(reparsed.header_len(), header.header_len());
+});
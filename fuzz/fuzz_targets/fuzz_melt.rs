@@ -0,0 +1,38 @@
+#![no_main]
+use imperator_save::{
+    FailedResolveStrategy, ImperatorFile, ImperatorMelt, ImperatorParallelMelt, MeltOptions,
+};
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+mod common;
+use common::TOKENS;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(file) = ImperatorFile::from_slice(data) else {
+        return;
+    };
+
+    for options in [
+        MeltOptions::new(),
+        MeltOptions::new()
+            .on_failed_resolve(FailedResolveStrategy::Stringify)
+            .compact(true),
+    ] {
+        let mut sequential = Vec::new();
+        let melted = (&file).melt(options.clone(), &*TOKENS, &mut sequential);
+
+        // The alternative melters must agree with the sequential output
+        let mut parallel = Vec::new();
+        let result = (&file).melt_parallel(options.clone(), &*TOKENS, &mut parallel, 3);
+        if melted.is_ok() && result.is_ok() {
+            assert_eq!(sequential, parallel);
+        }
+
+        let mut seekable = Cursor::new(Vec::new());
+        let result = (&file).melt_seekable(options, &*TOKENS, &mut seekable);
+        if melted.is_ok() && result.is_ok() {
+            assert_eq!(sequential, seekable.into_inner());
+        }
+    }
+});
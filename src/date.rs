@@ -1,2 +1,3 @@
 pub use jomini::common::Date as ImperatorDate;
+pub use jomini::common::DateFormat;
 pub use jomini::common::PdsDate;
@@ -0,0 +1,305 @@
+use crate::{ImperatorError, ImperatorFile, ImperatorMelt, MeltOptions};
+use jomini::{
+    binary::TokenResolver,
+    envelope::{ReaderAt, SaveHeader},
+    text::{ObjectReader, TextToken, ValueReader},
+    TextTape, Utf8Encoding,
+};
+use std::{borrow::Cow, collections::HashMap, fmt};
+
+/// Whether a field was added, removed, or changed between two saves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A single difference between two saves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    path: String,
+    kind: ChangeKind,
+    old: Option<String>,
+    new: Option<String>,
+}
+
+impl Change {
+    /// The dot delimited path to the field. Array elements are identified by
+    /// their index, and keys that occur multiple times within an object are
+    /// suffixed with their occurrence, e.g. `ai_task[2]`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The top level key that the change occurred under
+    pub fn section(&self) -> &str {
+        let end = self.path.find(['.', '[']).unwrap_or(self.path.len());
+        &self.path[..end]
+    }
+
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    /// The value in the old save. Containers are abbreviated.
+    pub fn old_value(&self) -> Option<&str> {
+        self.old.as_deref()
+    }
+
+    /// The value in the new save. Containers are abbreviated.
+    pub fn new_value(&self) -> Option<&str> {
+        self.new.as_deref()
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let old = self.old.as_deref().unwrap_or_default();
+        let new = self.new.as_deref().unwrap_or_default();
+        match self.kind {
+            ChangeKind::Added => write!(f, "+ {} = {}", self.path, new),
+            ChangeKind::Removed => write!(f, "- {} = {}", self.path, old),
+            ChangeKind::Changed => write!(f, "~ {}: {} -> {}", self.path, old, new),
+        }
+    }
+}
+
+/// Structural differences between two saves.
+///
+/// Objects are compared by key rather than by position, so database entries
+/// (which are keyed by their id) that are written in a different order are
+/// not reported. Arrays are compared element by element.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveDiff {
+    changes: Vec<Change>,
+}
+
+impl SaveDiff {
+    /// Melt both saves and compare them
+    pub fn from_files<R1, R2, Resolver>(
+        old: &ImperatorFile<R1>,
+        new: &ImperatorFile<R2>,
+        resolver: Resolver,
+    ) -> Result<Self, ImperatorError>
+    where
+        R1: ReaderAt,
+        R2: ReaderAt,
+        Resolver: TokenResolver,
+    {
+        let old = melted_body(old, &resolver)?;
+        let new = melted_body(new, &resolver)?;
+        Self::from_text(&old, &new)
+    }
+
+    /// Compare the plaintext contents of two saves (without the save header)
+    pub fn from_text(old: &[u8], new: &[u8]) -> Result<Self, ImperatorError> {
+        let old = TextTape::from_slice(old)?;
+        let new = TextTape::from_slice(new)?;
+        let mut changes = Vec::new();
+        diff_objects("", &old.utf8_reader(), &new.utf8_reader(), &mut changes);
+        Ok(SaveDiff { changes })
+    }
+
+    /// The differences in the order they were encountered
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Returns true if the saves are structurally identical
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+fn melted_body<R, Resolver>(
+    file: &ImperatorFile<R>,
+    resolver: Resolver,
+) -> Result<Vec<u8>, ImperatorError>
+where
+    R: ReaderAt,
+    Resolver: TokenResolver,
+{
+    let mut file = file;
+    let mut out = Vec::new();
+    file.melt(MeltOptions::new(), resolver, &mut out)?;
+    let header_len = SaveHeader::from_slice(&out)?.header_len();
+    out.drain(..header_len);
+    Ok(out)
+}
+
+type Object<'data, 'tokens> = ObjectReader<'data, 'tokens, Utf8Encoding>;
+type Value<'data, 'tokens> = ValueReader<'data, 'tokens, Utf8Encoding>;
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Object,
+    Array {
+        empty: bool,
+    },
+    /// A value prefixed with a name like `rgb { 10 20 30 }`
+    Header,
+    Scalar,
+}
+
+fn shape(value: &Value) -> Shape {
+    match value.token() {
+        TextToken::Object { .. } | TextToken::MixedContainer => Shape::Object,
+        TextToken::Header(_) => Shape::Header,
+        TextToken::Array { .. } => Shape::Array {
+            empty: value.read_array().map(|x| x.is_empty()).unwrap_or(true),
+        },
+        _ => Shape::Scalar,
+    }
+}
+
+fn render(value: &Value) -> String {
+    match shape(value) {
+        Shape::Scalar => value.read_str().map(Cow::into_owned).unwrap_or_default(),
+        Shape::Array { empty: true } => String::from("{ }"),
+        Shape::Array { .. } => {
+            let Ok(values) = value.read_array() else {
+                return String::from("{ ... }");
+            };
+
+            let values = values.values().collect::<Vec<_>>();
+            if values.len() > 8 || values.iter().any(|x| shape(x) != Shape::Scalar) {
+                return String::from("{ ... }");
+            }
+
+            let values = values.iter().map(render).collect::<Vec<_>>();
+            format!("{{ {} }}", values.join(" "))
+        }
+        Shape::Object => String::from("{ ... }"),
+        Shape::Header => {
+            let name = value.read_str().map(Cow::into_owned).unwrap_or_default();
+            let values = value.read_array().ok();
+            let inner = values.as_ref().and_then(|x| x.values().nth(1));
+            match inner {
+                Some(inner) => format!("{} {}", name, render(&inner)),
+                None => name,
+            }
+        }
+    }
+}
+
+fn child_path(parent: &str, segment: &str) -> String {
+    if parent.is_empty() {
+        String::from(segment)
+    } else {
+        format!("{}.{}", parent, segment)
+    }
+}
+
+fn group_fields<'data, 'tokens>(
+    object: &Object<'data, 'tokens>,
+) -> Vec<(Cow<'data, str>, Vec<Value<'data, 'tokens>>)> {
+    let mut indices: HashMap<Cow<'data, str>, usize> = HashMap::new();
+    let mut groups: Vec<(Cow<'data, str>, Vec<Value>)> = Vec::new();
+    for (key, _op, value) in object.fields() {
+        let key = key.read_str();
+        match indices.get(&key) {
+            Some(&index) => groups[index].1.push(value),
+            None => {
+                indices.insert(key.clone(), groups.len());
+                groups.push((key, vec![value]));
+            }
+        }
+    }
+    groups
+}
+
+fn diff_objects(path: &str, old: &Object, new: &Object, changes: &mut Vec<Change>) {
+    let old_groups = group_fields(old);
+    let new_groups = group_fields(new);
+    let new_lookup: HashMap<&str, usize> = new_groups
+        .iter()
+        .enumerate()
+        .map(|(i, (key, _))| (key.as_ref(), i))
+        .collect();
+
+    let empty = Vec::new();
+    let mut seen = vec![false; new_groups.len()];
+    for (key, olds) in &old_groups {
+        let news = match new_lookup.get(key.as_ref()) {
+            Some(&index) => {
+                seen[index] = true;
+                &new_groups[index].1
+            }
+            None => &empty,
+        };
+        diff_fields(path, key, olds, news, changes);
+    }
+
+    for ((key, news), seen) in new_groups.iter().zip(seen) {
+        if !seen {
+            diff_fields(path, key, &empty, news, changes);
+        }
+    }
+}
+
+/// Compare the values of a key that may occur multiple times within an object
+fn diff_fields(path: &str, key: &str, olds: &[Value], news: &[Value], changes: &mut Vec<Change>) {
+    let len = olds.len().max(news.len());
+    for i in 0..len {
+        let field_path = if len > 1 {
+            child_path(path, &format!("{}[{}]", key, i))
+        } else {
+            child_path(path, key)
+        };
+        diff_values(field_path, olds.get(i), news.get(i), changes);
+    }
+}
+
+fn diff_values(path: String, old: Option<&Value>, new: Option<&Value>, changes: &mut Vec<Change>) {
+    let (old, new) = match (old, new) {
+        (Some(old), Some(new)) => (old, new),
+        (Some(old), None) => {
+            changes.push(Change {
+                path,
+                kind: ChangeKind::Removed,
+                old: Some(render(old)),
+                new: None,
+            });
+            return;
+        }
+        (None, Some(new)) => {
+            changes.push(Change {
+                path,
+                kind: ChangeKind::Added,
+                old: None,
+                new: Some(render(new)),
+            });
+            return;
+        }
+        (None, None) => return,
+    };
+
+    match (shape(old), shape(new)) {
+        (Shape::Object, Shape::Object)
+        | (Shape::Object, Shape::Array { empty: true })
+        | (Shape::Array { empty: true }, Shape::Object) => {
+            if let (Ok(old), Ok(new)) = (old.read_object(), new.read_object()) {
+                diff_objects(&path, &old, &new, changes);
+            }
+        }
+        (Shape::Array { .. }, Shape::Array { .. }) => {
+            if let (Ok(old), Ok(new)) = (old.read_array(), new.read_array()) {
+                let olds = old.values().collect::<Vec<_>>();
+                let news = new.values().collect::<Vec<_>>();
+                for i in 0..olds.len().max(news.len()) {
+                    let element_path = child_path(&path, &i.to_string());
+                    diff_values(element_path, olds.get(i), news.get(i), changes);
+                }
+            }
+        }
+        (Shape::Scalar, Shape::Scalar) if old.read_str().ok() == new.read_str().ok() => {}
+        (Shape::Header, Shape::Header) if render(old) == render(new) => {}
+        _ => changes.push(Change {
+            path,
+            kind: ChangeKind::Changed,
+            old: Some(render(old)),
+            new: Some(render(new)),
+        }),
+    }
+}
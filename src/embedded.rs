@@ -0,0 +1,28 @@
+use jomini::binary::TokenResolver;
+
+include!(concat!(env!("OUT_DIR"), "/embedded_tokens.rs"));
+
+/// A token resolver for Imperator binary saves backed by a table that was
+/// generated from the token file at `IMPERATOR_TOKENS` when the crate was
+/// built.
+///
+/// The table is indexed by token id, so resolving a token is a single array
+/// lookup. The build fails if `IMPERATOR_TOKENS` is not set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EmbeddedTokens;
+
+impl EmbeddedTokens {
+    pub fn new() -> Self {
+        EmbeddedTokens
+    }
+}
+
+impl TokenResolver for EmbeddedTokens {
+    fn resolve(&self, token: u16) -> Option<&str> {
+        TOKENS.get(usize::from(token)).copied().flatten()
+    }
+
+    fn is_empty(&self) -> bool {
+        TOKENS.is_empty()
+    }
+}
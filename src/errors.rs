@@ -41,6 +41,9 @@ pub enum ImperatorErrorKind {
     #[error("unknown binary token encountered: {token_id:#x}")]
     UnknownToken { token_id: u32 },
 
+    #[error("unable to redact {key} as the resolver does not know its binary token")]
+    UnresolvedRedaction { key: String },
+
     #[error("invalid header")]
     InvalidHeader,
 
@@ -49,6 +52,58 @@ pub enum ImperatorErrorKind {
 
     #[error("invalid syntax: {0}")]
     InvalidSyntax(String),
+
+    #[error("header declares {kind:?} which does not match the file layout")]
+    HeaderKindMismatch {
+        kind: jomini::envelope::SaveHeaderKind,
+    },
+
+    #[error("header declares {declared} bytes of metadata but found {actual}")]
+    MetadataLength { declared: u64, actual: u64 },
+
+    #[error("zip is missing the {name} entry")]
+    MissingZipEntry { name: String },
+
+    #[error("gamestate is missing the {name} section")]
+    MissingSection { name: String },
+
+    #[error("metadata {field} ({metadata}) does not match the gamestate ({gamestate})")]
+    MetadataMismatch {
+        field: &'static str,
+        metadata: String,
+        gamestate: String,
+    },
+
+    #[error("{} belongs to a different playthrough", path.display())]
+    PlaythroughMismatch { path: std::path::PathBuf },
+
+    #[error("invalid token file: {0}")]
+    InvalidTokens(String),
+
+    #[error("unable to read token file {}: {source}", path.display())]
+    TokenFile {
+        path: std::path::PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("no token file found, set IMPERATOR_TOKENS or create one of: {}", searched.iter().map(|x| x.display().to_string()).collect::<Vec<_>>().join(", "))]
+    TokensNotFound { searched: Vec<std::path::PathBuf> },
+
+    #[error("binary and text saves diverge at byte {position}: {msg}")]
+    TokenAlignment { position: usize, msg: String },
+
+    #[error("token inference requires a {expected} save but received a {actual} save")]
+    InferenceEncoding {
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    #[error("zip error: {0}")]
+    Zip(#[from] rawzip::Error),
+
+    #[error("unable to stream zip: {0}")]
+    ZipStream(String),
 }
 
 impl From<jomini::Error> for ImperatorError {
@@ -89,6 +144,12 @@ impl From<jomini::envelope::EnvelopeError> for ImperatorError {
     }
 }
 
+impl From<rawzip::Error> for ImperatorError {
+    fn from(value: rawzip::Error) -> Self {
+        ImperatorError::from(ImperatorErrorKind::from(value))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
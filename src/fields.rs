@@ -0,0 +1,235 @@
+use crate::ImperatorError;
+use jomini::{binary, text};
+use std::ops::Range;
+
+/// The key of a top level field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FieldKey {
+    /// A binary token that the caller resolves
+    Id(u16),
+    Name(String),
+    /// A binary integer key
+    Int,
+}
+
+/// A top level `key=value` field
+#[derive(Debug, Clone)]
+pub(crate) struct Field {
+    pub key: FieldKey,
+
+    /// Offset of the key
+    pub start: usize,
+
+    /// The contents of a container value, excluding its braces
+    pub contents: Option<Range<usize>>,
+
+    /// Whether every token before the field belonged to a `key=value`
+    /// field, so that the data can be split at the field without changing
+    /// how it is interpreted
+    pub aligned: bool,
+}
+
+enum Lexeme {
+    Key(FieldKey),
+    Equal,
+    Open,
+    Close,
+    /// A token that is only expected as a value
+    Value,
+}
+
+/// The parts of the binary and text token readers needed to scan fields
+trait LexemeReader {
+    const CLOSE_LEN: usize;
+    fn next_lexeme(&mut self) -> Result<Option<Lexeme>, ImperatorError>;
+    fn position(&self) -> usize;
+    fn skip_container(&mut self) -> Result<(), ImperatorError>;
+}
+
+impl LexemeReader for binary::TokenReader<&'_ [u8]> {
+    const CLOSE_LEN: usize = 2;
+
+    fn next_lexeme(&mut self) -> Result<Option<Lexeme>, ImperatorError> {
+        let lexeme = match self.next()? {
+            None => return Ok(None),
+            Some(binary::Token::Id(id)) => Lexeme::Key(FieldKey::Id(id)),
+            Some(binary::Token::Quoted(x) | binary::Token::Unquoted(x)) => {
+                Lexeme::Key(FieldKey::Name(x.to_string()))
+            }
+            Some(
+                binary::Token::U32(_)
+                | binary::Token::I32(_)
+                | binary::Token::U64(_)
+                | binary::Token::I64(_),
+            ) => Lexeme::Key(FieldKey::Int),
+            Some(binary::Token::Equal) => Lexeme::Equal,
+            Some(binary::Token::Open) => Lexeme::Open,
+            Some(binary::Token::Close) => Lexeme::Close,
+            Some(_) => Lexeme::Value,
+        };
+        Ok(Some(lexeme))
+    }
+
+    fn position(&self) -> usize {
+        self.position()
+    }
+
+    fn skip_container(&mut self) -> Result<(), ImperatorError> {
+        Ok(self.skip_container()?)
+    }
+}
+
+impl LexemeReader for text::TokenReader<&'_ [u8]> {
+    const CLOSE_LEN: usize = 1;
+
+    fn next_lexeme(&mut self) -> Result<Option<Lexeme>, ImperatorError> {
+        let lexeme = match self.next().map_err(jomini::Error::from)? {
+            None => return Ok(None),
+            Some(text::Token::Unquoted(x) | text::Token::Quoted(x)) => {
+                Lexeme::Key(FieldKey::Name(x.to_string()))
+            }
+            Some(text::Token::Operator(text::Operator::Equal)) => Lexeme::Equal,
+            Some(text::Token::Operator(_)) => Lexeme::Value,
+            Some(text::Token::Open) => Lexeme::Open,
+            Some(text::Token::Close) => Lexeme::Close,
+        };
+        Ok(Some(lexeme))
+    }
+
+    fn position(&self) -> usize {
+        self.position()
+    }
+
+    fn skip_container(&mut self) -> Result<(), ImperatorError> {
+        Ok(self.skip_container().map_err(jomini::Error::from)?)
+    }
+}
+
+/// Scan the top level fields of binary data. When `key_read` is set, the data
+/// starts immediately after the key of its first field.
+pub(crate) fn binary_fields(
+    data: &[u8],
+    key_read: bool,
+) -> impl Iterator<Item = Result<Field, ImperatorError>> + '_ {
+    let pending = key_read.then_some((0, FieldKey::Int));
+    Fields::new(binary::TokenReader::from_slice(data), pending)
+}
+
+/// Scan the top level fields of text data
+pub(crate) fn text_fields(data: &[u8]) -> impl Iterator<Item = Result<Field, ImperatorError>> + '_ {
+    Fields::new(text::TokenReader::from_slice(data), None)
+}
+
+/// Iterates over top level `key=value` fields. Tokens that aren't part of a
+/// field, like a stray closing brace or a value without a key, are skipped
+/// and clear [`Field::aligned`] for the remaining fields.
+struct Fields<R> {
+    reader: R,
+    pending: Option<(usize, FieldKey)>,
+    aligned: bool,
+    done: bool,
+}
+
+impl<R: LexemeReader> Fields<R> {
+    fn new(reader: R, pending: Option<(usize, FieldKey)>) -> Self {
+        Fields {
+            reader,
+            pending,
+            aligned: true,
+            done: false,
+        }
+    }
+
+    fn next_field(&mut self) -> Result<Option<Field>, ImperatorError> {
+        loop {
+            let (start, key) = match self.pending.take() {
+                Some(x) => x,
+                None => {
+                    let start = self.reader.position();
+                    match self.reader.next_lexeme()? {
+                        None => return Ok(None),
+                        Some(Lexeme::Key(key)) => (start, key),
+                        Some(Lexeme::Open) => {
+                            self.reader.skip_container()?;
+                            self.aligned = false;
+                            continue;
+                        }
+                        Some(_) => {
+                            self.aligned = false;
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let position = self.reader.position();
+            match self.reader.next_lexeme()? {
+                Some(Lexeme::Equal) => {}
+                None => return Ok(None),
+                Some(Lexeme::Key(key)) => {
+                    self.aligned = false;
+                    self.pending = Some((position, key));
+                    continue;
+                }
+                Some(Lexeme::Open) => {
+                    self.reader.skip_container()?;
+                    self.aligned = false;
+                    continue;
+                }
+                Some(_) => {
+                    self.aligned = false;
+                    continue;
+                }
+            }
+
+            let aligned = self.aligned;
+            let contents = match self.reader.next_lexeme()? {
+                None => return Ok(None),
+                Some(Lexeme::Open) => {
+                    let open = self.reader.position();
+                    self.reader.skip_container()?;
+                    Some(open..self.reader.position() - R::CLOSE_LEN)
+                }
+                Some(Lexeme::Key(_) | Lexeme::Value) => {
+                    // A value may be followed by its own container, as in
+                    // `color=hsv { 0.5 0.5 0.5 }`
+                    let position = self.reader.position();
+                    match self.reader.next_lexeme()? {
+                        Some(Lexeme::Open) => self.reader.skip_container()?,
+                        Some(Lexeme::Key(key)) => self.pending = Some((position, key)),
+                        Some(_) => self.aligned = false,
+                        None => self.done = true,
+                    }
+                    None
+                }
+                Some(Lexeme::Equal | Lexeme::Close) => {
+                    self.aligned = false;
+                    continue;
+                }
+            };
+
+            return Ok(Some(Field {
+                key,
+                start,
+                contents,
+                aligned,
+            }));
+        }
+    }
+}
+
+impl<R: LexemeReader> Iterator for Fields<R> {
+    type Item = Result<Field, ImperatorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.next_field().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
@@ -1,11 +1,14 @@
-use crate::{flavor::ImperatorFlavor, melt, ImperatorError, ImperatorErrorKind, MeltOptions};
+use crate::{
+    flavor::ImperatorFlavor, melt, models::Metadata, ImperatorError, ImperatorErrorKind,
+    MeltOptions,
+};
 use jomini::{
     binary::{de::BinaryReaderDeserializer, BinaryDeserializerBuilder, TokenResolver},
     text::de::TextReaderDeserializer,
     Utf8Encoding,
 };
 use serde::de::DeserializeOwned;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, Write};
 
 pub use jomini::envelope::JominiFile as ImperatorFile;
 pub use jomini::envelope::*;
@@ -62,6 +65,47 @@ pub trait ImperatorMelt {
     where
         Resolver: TokenResolver,
         Writer: Write;
+
+    /// Melt directly into a seekable output without buffering the metadata in
+    /// memory. The header's metadata length is patched in place once the
+    /// metadata has been written.
+    ///
+    /// The default implementation melts into memory and then writes the
+    /// result, so implementors only need to provide [`ImperatorMelt::melt`].
+    fn melt_seekable<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        mut output: Writer,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write + Seek,
+    {
+        let mut buf = Vec::new();
+        let doc = self.melt(options, resolver, &mut buf)?;
+        output.write_all(&buf)?;
+        Ok(doc)
+    }
+}
+
+pub trait ImperatorParallelMelt {
+    /// Melt with the gamestate split at its top level fields into up to
+    /// `threads` chunks that are melted concurrently. The output is
+    /// identical to [`ImperatorMelt::melt`], at the cost of holding the
+    /// binary gamestate and the melted chunks in memory.
+    ///
+    /// [`std::thread::available_parallelism`] is a reasonable thread count.
+    fn melt_parallel<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        output: Writer,
+        threads: usize,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver + Sync,
+        Writer: Write;
 }
 
 pub trait ImperatorTextMelt {
@@ -83,11 +127,7 @@ impl<R: ReaderAt> ImperatorMelt for &'_ ImperatorFile<R> {
     {
         match self.gamestate().map_err(ImperatorErrorKind::from)? {
             SaveContentKind::Text(mut save_body) => {
-                let mut new_header = self.header().clone();
-                new_header.set_kind(SaveHeaderKind::Text);
-                new_header.write(&mut output)?;
-                std::io::copy(&mut save_body, &mut output)?;
-                Ok(melt::MeltedDocument::new())
+                melt::melt_text(self.header(), &mut save_body, &mut output)
             }
             SaveContentKind::Binary(mut save_body) => melt::melt(
                 &mut save_body,
@@ -98,6 +138,62 @@ impl<R: ReaderAt> ImperatorMelt for &'_ ImperatorFile<R> {
             ),
         }
     }
+
+    fn melt_seekable<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        mut output: Writer,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write + Seek,
+    {
+        match self.gamestate().map_err(ImperatorErrorKind::from)? {
+            SaveContentKind::Text(mut save_body) => {
+                melt::melt_text(self.header(), &mut save_body, &mut output)
+            }
+            SaveContentKind::Binary(mut save_body) => melt::melt_seekable(
+                &mut save_body,
+                &mut output,
+                resolver,
+                options,
+                self.header().clone(),
+            ),
+        }
+    }
+}
+
+impl<R: ReaderAt> ImperatorParallelMelt for &'_ ImperatorFile<R> {
+    fn melt_parallel<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        mut output: Writer,
+        threads: usize,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver + Sync,
+        Writer: Write,
+    {
+        match self.gamestate().map_err(ImperatorErrorKind::from)? {
+            SaveContentKind::Text(mut save_body) => {
+                melt::melt_text(self.header(), &mut save_body, &mut output)
+            }
+            SaveContentKind::Binary(mut save_body) => {
+                let mut data = Vec::new();
+                save_body.read_to_end(&mut data)?;
+                melt::melt_parallel(
+                    &data,
+                    &mut output,
+                    resolver,
+                    options,
+                    self.header().clone(),
+                    threads,
+                )
+            }
+        }
+    }
 }
 
 impl<R: ReaderAt> ImperatorMelt for &'_ JominiZip<R> {
@@ -113,11 +209,7 @@ impl<R: ReaderAt> ImperatorMelt for &'_ JominiZip<R> {
     {
         match self.gamestate().map_err(ImperatorErrorKind::from)? {
             SaveContentKind::Text(mut save_body) => {
-                let mut new_header = self.header().clone();
-                new_header.set_kind(SaveHeaderKind::Text);
-                new_header.write(&mut output)?;
-                std::io::copy(&mut save_body, &mut output)?;
-                Ok(melt::MeltedDocument::new())
+                melt::melt_text(self.header(), &mut save_body, &mut output)
             }
             SaveContentKind::Binary(mut save_body) => melt::melt(
                 &mut save_body,
@@ -128,6 +220,30 @@ impl<R: ReaderAt> ImperatorMelt for &'_ JominiZip<R> {
             ),
         }
     }
+
+    fn melt_seekable<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        mut output: Writer,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write + Seek,
+    {
+        match self.gamestate().map_err(ImperatorErrorKind::from)? {
+            SaveContentKind::Text(mut save_body) => {
+                melt::melt_text(self.header(), &mut save_body, &mut output)
+            }
+            SaveContentKind::Binary(mut save_body) => melt::melt_seekable(
+                &mut save_body,
+                &mut output,
+                resolver,
+                options,
+                self.header().clone(),
+            ),
+        }
+    }
 }
 
 impl<R: ReaderAt> ImperatorMelt for &'_ SaveData<BinaryEncoding, R> {
@@ -149,6 +265,25 @@ impl<R: ReaderAt> ImperatorMelt for &'_ SaveData<BinaryEncoding, R> {
             self.header().clone(),
         )
     }
+
+    fn melt_seekable<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        mut output: Writer,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write + Seek,
+    {
+        melt::melt_seekable(
+            &mut self.body().cursor(),
+            &mut output,
+            resolver,
+            options,
+            self.header().clone(),
+        )
+    }
 }
 
 impl<R: Read> ImperatorMelt for SaveMetadataKind<R> {
@@ -167,6 +302,22 @@ impl<R: Read> ImperatorMelt for SaveMetadataKind<R> {
             SaveMetadataKind::Binary(x) => x.melt(options, resolver, output),
         }
     }
+
+    fn melt_seekable<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        output: Writer,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write + Seek,
+    {
+        match self {
+            SaveMetadataKind::Text(x) => x.melt(output),
+            SaveMetadataKind::Binary(x) => x.melt_seekable(options, resolver, output),
+        }
+    }
 }
 
 impl<R: ReaderAt> ImperatorTextMelt for &'_ SaveData<TextEncoding, R> {
@@ -174,11 +325,7 @@ impl<R: ReaderAt> ImperatorTextMelt for &'_ SaveData<TextEncoding, R> {
     where
         Writer: Write,
     {
-        let mut new_header = self.header().clone();
-        new_header.set_kind(SaveHeaderKind::Text);
-        new_header.write(&mut output)?;
-        std::io::copy(&mut self.body().cursor(), &mut output)?;
-        Ok(melt::MeltedDocument::new())
+        melt::melt_text(self.header(), &mut self.body().cursor(), &mut output)
     }
 }
 
@@ -187,11 +334,8 @@ impl<R: Read> ImperatorTextMelt for SaveMetadata<TextEncoding, R> {
     where
         Writer: Write,
     {
-        let mut new_header = self.header().clone();
-        new_header.set_kind(SaveHeaderKind::Text);
-        new_header.write(&mut output)?;
-        std::io::copy(self, &mut output)?;
-        Ok(melt::MeltedDocument::new())
+        let header = self.header().clone();
+        melt::melt_text(&header, self, &mut output)
     }
 }
 
@@ -209,6 +353,20 @@ impl<R: Read> ImperatorMelt for SaveMetadata<BinaryEncoding, R> {
         let header = self.header().clone();
         melt::melt(self, output, resolver, options, header)
     }
+
+    fn melt_seekable<Resolver, Writer>(
+        &mut self,
+        options: MeltOptions,
+        resolver: Resolver,
+        output: Writer,
+    ) -> Result<melt::MeltedDocument, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write + Seek,
+    {
+        let header = self.header().clone();
+        melt::melt_seekable(self, output, resolver, options, header)
+    }
 }
 
 pub trait DeserializeImperator {
@@ -246,3 +404,34 @@ impl<R: ReaderAt> DeserializeImperator for &'_ ImperatorFile<R> {
         }
     }
 }
+
+pub trait ImperatorMetadata {
+    /// Deserialize only the metadata of a save. The metadata is read from
+    /// the region declared in the header or from the zip's `meta` entry, so
+    /// the gamestate is not decompressed or parsed.
+    ///
+    /// Uncompressed saves that do not declare a metadata region (like
+    /// those written in debug mode) have no metadata to read on its own.
+    /// For these saves, the metadata fields are deserialized from the whole
+    /// save, which costs as much as a full deserialization.
+    fn metadata(&self, resolver: impl TokenResolver) -> Result<Metadata, ImperatorError>;
+}
+
+impl<R: ReaderAt> ImperatorMetadata for ImperatorFile<R> {
+    fn metadata(&self, resolver: impl TokenResolver) -> Result<Metadata, ImperatorError> {
+        if let JominiFileKind::Uncompressed(_) = self.kind() {
+            // The metadata fields are at the start of the save, but
+            // deserializing them still reads through to the end
+            if self.header().metadata_len() == 0 {
+                let mut file = self;
+                return file.deserialize(resolver);
+            }
+        }
+
+        let meta = match self.meta()? {
+            SaveMetadataKind::Text(mut x) => x.deserializer().deserialize(),
+            SaveMetadataKind::Binary(mut x) => x.deserializer(&resolver).deserialize(),
+        };
+        Ok(meta.map_err(ImperatorErrorKind::Deserialize)?)
+    }
+}
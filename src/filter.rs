@@ -0,0 +1,85 @@
+use std::{fmt, sync::Arc};
+
+/// A field encountered while melting, passed to a field filter to decide what
+/// should be written out.
+#[derive(Debug, Clone, Copy)]
+pub struct MeltField<'a> {
+    parent: &'a str,
+    key: &'a str,
+}
+
+impl<'a> MeltField<'a> {
+    pub(crate) fn new(parent: &'a str, key: &'a str) -> Self {
+        MeltField { parent, key }
+    }
+
+    /// The resolved key of the field
+    pub fn key(&self) -> &'a str {
+        self.key
+    }
+
+    /// The dot delimited path of the object containing the field. Empty for
+    /// top level fields.
+    pub fn parent_path(&self) -> &'a str {
+        self.parent
+    }
+
+    /// The dot delimited path of the field, e.g. `family.12.name`
+    pub fn path(&self) -> String {
+        if self.parent.is_empty() {
+            String::from(self.key)
+        } else {
+            format!("{}.{}", self.parent, self.key)
+        }
+    }
+}
+
+/// What the melter should do with a field
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FieldAction {
+    /// Write the field as is
+    #[default]
+    Keep,
+
+    /// Omit the field and its value from the output
+    Drop,
+
+    /// Write the value under a different key
+    Rename(String),
+
+    /// Keep the key but replace the value (whether a scalar or a container)
+    /// with the given quoted string
+    Redact(String),
+}
+
+/// Shareable callback that decides the [`FieldAction`] for a field
+#[derive(Clone)]
+pub(crate) struct FieldFilter(Arc<dyn Fn(&MeltField) -> FieldAction + Send + Sync>);
+
+impl FieldFilter {
+    pub(crate) fn new<F>(filter: F) -> Self
+    where
+        F: Fn(&MeltField) -> FieldAction + Send + Sync + 'static,
+    {
+        FieldFilter(Arc::new(filter))
+    }
+
+    pub(crate) fn action(&self, field: &MeltField) -> FieldAction {
+        (self.0)(field)
+    }
+}
+
+/// Filters are equal only when they share the same callback
+impl PartialEq for FieldFilter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for FieldFilter {}
+
+impl fmt::Debug for FieldFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FieldFilter")
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{ImperatorError, ImperatorErrorKind, ImperatorFile, TokenTable};
+use jomini::{
+    binary,
+    envelope::{ReaderAt, SaveContentKind},
+    text::{self, Operator},
+};
+use std::io::Read;
+
+/// An id that was aligned with a token different from what was already
+/// known or previously inferred for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenConflict {
+    id: u16,
+    existing: String,
+    inferred: String,
+}
+
+impl TokenConflict {
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// The token that was kept for the id
+    pub fn existing(&self) -> &str {
+        &self.existing
+    }
+
+    /// The token that the id was aligned with
+    pub fn inferred(&self) -> &str {
+        &self.inferred
+    }
+}
+
+/// The result of aligning a binary save with the same save written as text
+#[derive(Debug, Clone, Default)]
+pub struct TokenInference {
+    tokens: TokenTable,
+    inferred: Vec<u16>,
+    conflicts: Vec<TokenConflict>,
+}
+
+impl TokenInference {
+    /// Infer tokens by aligning the gamestate of a binary save with the
+    /// gamestate of the same save exported as text. Tokens in `known` are
+    /// kept and any disagreement with them is reported as a conflict.
+    ///
+    /// An error is returned if either save is not of the expected encoding
+    /// or if the two saves diverge structurally.
+    pub fn from_files<R1, R2>(
+        binary: &ImperatorFile<R1>,
+        text: &ImperatorFile<R2>,
+        known: &TokenTable,
+    ) -> Result<Self, ImperatorError>
+    where
+        R1: ReaderAt,
+        R2: ReaderAt,
+    {
+        let binary_data = match binary.gamestate()? {
+            SaveContentKind::Binary(mut x) => read_all(&mut x)?,
+            SaveContentKind::Text(_) => return Err(encoding("binary", "text")),
+        };
+
+        let text_data = match text.gamestate()? {
+            SaveContentKind::Text(mut x) => read_all(&mut x)?,
+            SaveContentKind::Binary(_) => return Err(encoding("text", "binary")),
+        };
+
+        Self::from_slices(&binary_data, &text_data, known)
+    }
+
+    /// Infer tokens by aligning binary and text encoded data (without the
+    /// save header)
+    pub fn from_slices(
+        binary_data: &[u8],
+        text_data: &[u8],
+        known: &TokenTable,
+    ) -> Result<Self, ImperatorError> {
+        let mut result = TokenInference {
+            tokens: known.clone(),
+            ..TokenInference::default()
+        };
+
+        let mut binary_reader = binary::TokenReader::from_slice(binary_data);
+        let mut text_reader = text::TokenReader::from_slice(text_data);
+        loop {
+            let position = binary_reader.position();
+            let expected = binary_reader.next()?;
+            let misaligned = |msg: &str| ImperatorErrorKind::TokenAlignment {
+                position,
+                msg: String::from(msg),
+            };
+
+            let Some(expected) = expected else {
+                return match next_text(&mut text_reader)? {
+                    None => Ok(result),
+                    Some(_) => Err(misaligned("text save has trailing data").into()),
+                };
+            };
+
+            let actual =
+                next_text(&mut text_reader)?.ok_or_else(|| misaligned("text save ended early"))?;
+            match (expected, actual) {
+                (binary::Token::Open, text::Token::Open)
+                | (binary::Token::Close, text::Token::Close)
+                | (binary::Token::Equal, text::Token::Operator(Operator::Equal)) => {}
+                (binary::Token::Id(id), text::Token::Unquoted(x) | text::Token::Quoted(x)) => {
+                    result.observe(id, String::from_utf8_lossy(x.as_bytes()).into_owned());
+                }
+                (binary::Token::Rgb(_), text::Token::Unquoted(x)) if x.as_bytes() == b"rgb" => {
+                    let mut shape = [false; 5];
+                    for (i, matched) in shape.iter_mut().enumerate() {
+                        *matched = match next_text(&mut text_reader)? {
+                            Some(text::Token::Open) => i == 0,
+                            Some(text::Token::Close) => i == 4,
+                            Some(x) => x.as_scalar().is_some() && i != 0 && i != 4,
+                            None => false,
+                        };
+                    }
+                    if shape.contains(&false) {
+                        return Err(misaligned("expected an rgb value").into());
+                    }
+                }
+                (
+                    binary::Token::Open
+                    | binary::Token::Close
+                    | binary::Token::Equal
+                    | binary::Token::Id(_)
+                    | binary::Token::Rgb(_),
+                    _,
+                ) => return Err(misaligned("token kinds differ").into()),
+                (_, text::Token::Unquoted(_) | text::Token::Quoted(_)) => {}
+                (_, _) => return Err(misaligned("expected a scalar").into()),
+            }
+        }
+    }
+
+    fn observe(&mut self, id: u16, token: String) {
+        match self.tokens.get(id) {
+            Some(existing) if existing == token => {}
+            Some(existing) => {
+                let existing = String::from(existing);
+                let conflict = self
+                    .conflicts
+                    .iter()
+                    .any(|x| x.id == id && x.inferred == token);
+                if !conflict {
+                    self.conflicts.push(TokenConflict {
+                        id,
+                        existing,
+                        inferred: token,
+                    });
+                }
+            }
+            None => {
+                self.tokens.insert(id, token);
+                self.inferred.push(id);
+            }
+        }
+    }
+
+    /// The known tokens combined with the newly inferred tokens
+    pub fn tokens(&self) -> &TokenTable {
+        &self.tokens
+    }
+
+    /// Tokens that were not previously known, in the order first seen
+    pub fn inferred(&self) -> impl Iterator<Item = (u16, &str)> + '_ {
+        self.inferred
+            .iter()
+            .filter_map(|&id| self.tokens.get(id).map(|x| (id, x)))
+    }
+
+    pub fn conflicts(&self) -> &[TokenConflict] {
+        &self.conflicts
+    }
+}
+
+fn next_text<'a>(
+    reader: &'a mut text::TokenReader<&[u8]>,
+) -> Result<Option<text::Token<'a>>, ImperatorError> {
+    Ok(reader.next().map_err(jomini::Error::from)?)
+}
+
+fn encoding(expected: &'static str, actual: &'static str) -> ImperatorError {
+    ImperatorErrorKind::InferenceEncoding { expected, actual }.into()
+}
+
+fn read_all<R: Read>(reader: &mut R) -> Result<Vec<u8>, ImperatorError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    Ok(data)
+}
@@ -6,7 +6,7 @@ Imperator Save is a library to ergonomically work with Imperator Rome saves (deb
 To load and parse a save file:
 
 ```rust,ignore
-use imperator_save::{ImperatorFile, models::Save, BasicTokenResolver};
+use imperator_save::{ImperatorFile, models::Save, BasicTokenResolver, GameVersion};
 use imperator_save::DeserializeImperator;
 
 // Load the file
@@ -19,7 +19,7 @@ let tokens = BasicTokenResolver::from_text_lines(&b""[..]).unwrap();
 
 // Parse the save
 let save: Save = (&file).deserialize(tokens)?;
-assert_eq!(save.meta.version, String::from("1.5.3"));
+assert_eq!(save.meta.version, GameVersion::new(1, 5, 3));
 # Ok::<(), Box<dyn std::error::Error>>(())
 ```
 
@@ -27,17 +27,58 @@ assert_eq!(save.meta.version, String::from("1.5.3"));
 
 Ironman saves are supported through a provided `TokenResolver`. Per PDS counsel, the data to construct such a `TokenResolver` is not distributed here.
 
+A `TokenTable` can be loaded at runtime with `TokenTable::from_env`. Alternatively, enable the `embedded-tokens` feature and set `IMPERATOR_TOKENS` to the path of a token file when building to bake the tokens into the binary as `EmbeddedTokens`. The build fails if the feature is enabled without `IMPERATOR_TOKENS`.
+
+## Async
+
+The `async` feature adds `ImperatorAsyncReader`, which reads the header and metadata from an `AsyncRead` and decompresses the gamestate as it streams in, without first buffering the whole file.
+
+## Command line
+
+The `imperator-save` command line tool, for melting, summarizing, and validating saves among other things, is built with the `cli` feature.
+
 */
 
 mod date;
+mod diff;
+#[cfg(feature = "embedded-tokens")]
+mod embedded;
 mod errors;
+mod fields;
 mod file;
+mod filter;
 mod flavor;
+mod infer;
 mod melt;
 pub mod models;
+mod redact;
+mod schema;
+mod sections;
+#[cfg(feature = "async")]
+mod stream;
+mod summary;
+mod timeline;
+mod tokens;
+mod validate;
+mod version;
 
 pub use date::*;
+pub use diff::*;
+#[cfg(feature = "embedded-tokens")]
+pub use embedded::*;
 pub use errors::*;
 pub use file::*;
+pub use filter::{FieldAction, MeltField};
+pub use infer::*;
 pub use jomini::binary::{BasicTokenResolver, FailedResolveStrategy};
 pub use melt::*;
+pub use redact::*;
+pub use schema::*;
+pub use sections::*;
+#[cfg(feature = "async")]
+pub use stream::*;
+pub use summary::*;
+pub use timeline::*;
+pub use tokens::*;
+pub use validate::*;
+pub use version::*;
@@ -0,0 +1,568 @@
+use imperator_save::{
+    FailedResolveStrategy, ImperatorError, ImperatorErrorKind, ImperatorFile, ImperatorMelt,
+    ImperatorValidate, MeltOptions, PdsDate, PlayerSummary, SaveDiff, SaveHeader, SaveSummary,
+    TokenFormat, TokenInference, TokenTable,
+};
+use jomini::{binary::TokenResolver, envelope::JominiFileKind, TextTape};
+use rawzip::FileReader;
+use std::{
+    cell::Cell,
+    fmt,
+    io::{BufWriter, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+const USAGE: &str = "\
+Usage: imperator-save <command> [options] <save>...
+
+Commands:
+  melt <save>                    Convert a save to plaintext
+  json <save>                    Convert a save to JSON
+  info <save>                    Print a summary of a save and its player
+  validate <save>                Check the integrity of a save
+  extract <save>                 Write the decompressed contents of a save entry
+  diff <old> <new>               Print the structural differences between two saves
+  infer-tokens <binary> <text>   Infer tokens from a binary save and its text export
+
+Options:
+  --tokens <path>    Token file used to resolve binary saves. Defaults to the
+                     path in IMPERATOR_TOKENS or assets/imperator.txt
+  -o, --output <path>
+                     Write to the given file instead of stdout
+  --entry <name>     Entry written by extract: meta, gamestate (default), or
+                     the name of a zip entry
+  --format <format>  Output format of info: text (default) or json
+  -h, --help         Print this message
+
+Exit codes:
+  0  success
+  1  io or other error
+  2  invalid arguments
+  3  the save could not be parsed or failed validation
+  4  the save contains tokens that could not be resolved
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Melt,
+    Json,
+    Info,
+    Validate,
+    Extract,
+    Diff,
+    InferTokens,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Command> {
+        match name {
+            "melt" => Some(Command::Melt),
+            "json" => Some(Command::Json),
+            "info" => Some(Command::Info),
+            "validate" => Some(Command::Validate),
+            "extract" => Some(Command::Extract),
+            "diff" => Some(Command::Diff),
+            "infer-tokens" => Some(Command::InferTokens),
+            _ => None,
+        }
+    }
+
+    fn inputs(&self) -> usize {
+        match self {
+            Command::Diff | Command::InferTokens => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Args {
+    command: Command,
+    inputs: Vec<PathBuf>,
+    tokens: Option<PathBuf>,
+    output: Option<PathBuf>,
+    entry: String,
+    json: bool,
+    unresolved: Cell<Option<u16>>,
+}
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Save(ImperatorError),
+
+    /// A save error that may have been caused by a token that could not be
+    /// resolved
+    Unresolved {
+        source: ImperatorError,
+        token_id: u16,
+    },
+    Other(Box<dyn std::error::Error>),
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Other(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Unresolved { .. } => 4,
+            CliError::Save(e) => save_exit_code(e),
+        }
+    }
+}
+
+fn save_exit_code(e: &ImperatorError) -> u8 {
+    match e.kind() {
+        ImperatorErrorKind::UnknownToken { .. }
+        | ImperatorErrorKind::UnresolvedRedaction { .. } => 4,
+        ImperatorErrorKind::Io(_)
+        | ImperatorErrorKind::TokenFile { .. }
+        | ImperatorErrorKind::TokensNotFound { .. }
+        | ImperatorErrorKind::InvalidTokens(_) => 1,
+        _ => 3,
+    }
+}
+
+impl CliError {
+    /// Note the first token that could not be resolved on a save that failed
+    /// to parse, as binary saves are read without erroring on unknown tokens
+    /// and instead fail later with less helpful errors
+    fn unresolved(self, token: Option<u16>) -> CliError {
+        match (self, token) {
+            (CliError::Save(source), Some(token_id)) if save_exit_code(&source) == 3 => {
+                CliError::Unresolved { source, token_id }
+            }
+            (e, _) => e,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{}\n\n{}", msg, USAGE),
+            CliError::Save(e) => match e.kind() {
+                ImperatorErrorKind::UnknownToken { .. }
+                | ImperatorErrorKind::UnresolvedRedaction { .. } => {
+                    write!(f, "{} (supply a token file with --tokens)", e)
+                }
+                _ => write!(f, "{}", e),
+            },
+            CliError::Unresolved { source, token_id } => write!(
+                f,
+                "{} (the save contains the unresolved token {:#x}, supply a token file with --tokens)",
+                source, token_id
+            ),
+            CliError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<ImperatorError> for CliError {
+    fn from(value: ImperatorError) -> Self {
+        CliError::Save(value)
+    }
+}
+
+impl From<jomini::envelope::EnvelopeError> for CliError {
+    fn from(value: jomini::envelope::EnvelopeError) -> Self {
+        CliError::Save(ImperatorError::from(value))
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(value: std::io::Error) -> Self {
+        CliError::Other(Box::new(value))
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, CliError> {
+    let mut command = None;
+    let mut inputs = Vec::new();
+    let mut tokens = None;
+    let mut output = None;
+    let mut entry = None;
+    let mut format = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| CliError::Usage(format!("{} requires a value", name)))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--tokens" => tokens = Some(PathBuf::from(value(&arg)?)),
+            "-o" | "--output" => output = Some(PathBuf::from(value(&arg)?)),
+            "--entry" => entry = Some(value(&arg)?),
+            "--format" => format = Some(value(&arg)?),
+            x if x.starts_with('-') && x != "-" => {
+                return Err(CliError::Usage(format!("unrecognized option: {}", x)))
+            }
+            x if command.is_none() => {
+                let parsed = Command::from_name(x)
+                    .ok_or_else(|| CliError::Usage(format!("unrecognized command: {}", x)))?;
+                command = Some(parsed);
+            }
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    let Some(command) = command else {
+        return Err(CliError::Usage(String::from("expected a command")));
+    };
+
+    if inputs.len() != command.inputs() {
+        return Err(CliError::Usage(format!(
+            "expected {} save file(s) but received {}",
+            command.inputs(),
+            inputs.len()
+        )));
+    }
+
+    if entry.is_some() && command != Command::Extract {
+        return Err(CliError::Usage(String::from(
+            "--entry is only valid with extract",
+        )));
+    }
+
+    if format.is_some() && command != Command::Info {
+        return Err(CliError::Usage(String::from(
+            "--format is only valid with info",
+        )));
+    }
+
+    let json = match format.as_deref() {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(x) => return Err(CliError::Usage(format!("unrecognized format: {}", x))),
+    };
+
+    Ok(Some(Args {
+        command,
+        inputs,
+        tokens,
+        output,
+        entry: entry.unwrap_or_else(|| String::from("gamestate")),
+        json,
+        unresolved: Cell::new(None),
+    }))
+}
+
+impl Args {
+    /// An explicitly provided token file must load, otherwise binary saves
+    /// will report the tokens that could not be resolved
+    fn token_table(&self) -> Result<TokenTable, CliError> {
+        match &self.tokens {
+            Some(path) => Ok(TokenTable::from_path(path)?),
+            None => match TokenTable::from_env() {
+                Ok(tokens) => Ok(tokens),
+                Err(e) if matches!(e.kind(), ImperatorErrorKind::TokensNotFound { .. }) => {
+                    Ok(TokenTable::new())
+                }
+                Err(e) => Err(e.into()),
+            },
+        }
+    }
+
+    fn tokens(&self) -> Result<Tokens<'_>, CliError> {
+        Ok(Tokens {
+            table: self.token_table()?,
+            unresolved: &self.unresolved,
+        })
+    }
+
+    fn file(&self, index: usize) -> Result<std::fs::File, CliError> {
+        let path = &self.inputs[index];
+        std::fs::File::open(path).map_err(|e| {
+            CliError::Other(format!("unable to open {}: {}", path.display(), e).into())
+        })
+    }
+
+    fn open(&self, index: usize) -> Result<ImperatorFile<FileReader>, CliError> {
+        Ok(ImperatorFile::from_file(self.file(index)?)?)
+    }
+
+    fn output(&self) -> Result<Box<dyn Write>, CliError> {
+        match &self.output {
+            Some(path) => {
+                let file = std::fs::File::create(path).map_err(|e| {
+                    CliError::Other(format!("unable to create {}: {}", path.display(), e).into())
+                })?;
+                Ok(Box::new(BufWriter::new(file)))
+            }
+            None => Ok(Box::new(BufWriter::new(std::io::stdout().lock()))),
+        }
+    }
+}
+
+/// Resolves tokens from the table while remembering the first token that
+/// could not be resolved
+struct Tokens<'a> {
+    table: TokenTable,
+    unresolved: &'a Cell<Option<u16>>,
+}
+
+impl TokenResolver for Tokens<'_> {
+    fn resolve(&self, token: u16) -> Option<&str> {
+        let result = self.table.resolve(token);
+        if result.is_none() && self.unresolved.get().is_none() {
+            self.unresolved.set(Some(token));
+        }
+        result
+    }
+
+    fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+}
+
+fn write_summary<W: Write>(
+    mut output: W,
+    summary: &SaveSummary,
+    player: &PlayerSummary,
+) -> std::io::Result<()> {
+    writeln!(output, "version: {}", summary.version())?;
+    writeln!(output, "date: {}", summary.date().game_fmt())?;
+    if let Some(name) = player.name() {
+        writeln!(output, "player: {}", name)?;
+    }
+    if let Some(tag) = player.tag() {
+        writeln!(output, "tag: {}", tag)?;
+    }
+    if let Some(country) = summary.country_name() {
+        writeln!(output, "country: {}", country)?;
+    }
+    writeln!(output, "ironman: {}", summary.ironman())?;
+    writeln!(output, "dlcs: {}", summary.dlcs().join(", "))?;
+    writeln!(output, "play time: {}s", summary.play_time())?;
+    writeln!(output, "kind: {:?}", summary.kind())?;
+    writeln!(output, "file size: {}", summary.file_size())?;
+    writeln!(output, "uncompressed size: {}", summary.uncompressed_size())?;
+    for entry in summary.entries() {
+        writeln!(
+            output,
+            "entry: {} ({} / {})",
+            entry.name(),
+            entry.compressed_size(),
+            entry.uncompressed_size()
+        )?;
+    }
+    Ok(())
+}
+
+/// The summary as JSON, built from its accessors so that the CLI does not
+/// depend on the `serialize` feature
+fn summary_json(summary: &SaveSummary, player: &PlayerSummary) -> serde_json::Value {
+    let entries: Vec<_> = summary
+        .entries()
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "name": entry.name(),
+                "compressed_size": entry.compressed_size(),
+                "uncompressed_size": entry.uncompressed_size(),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "version": summary.version().to_string(),
+        "date": summary.date().game_fmt().to_string(),
+        "player_name": player.name(),
+        "player_tag": player.tag(),
+        "country_name": summary.country_name(),
+        "ironman": summary.ironman(),
+        "dlcs": summary.dlcs(),
+        "play_time": summary.play_time(),
+        "kind": format!("{:?}", summary.kind()),
+        "file_size": summary.file_size(),
+        "uncompressed_size": summary.uncompressed_size(),
+        "entries": entries,
+    })
+}
+
+fn run(args: &Args) -> Result<(), CliError> {
+    match args.command {
+        Command::Melt => {
+            let file = args.open(0)?;
+            let options = MeltOptions::new().on_failed_resolve(FailedResolveStrategy::Error);
+            let mut output = args.output()?;
+            (&file).melt(options, &args.tokens()?, &mut output)?;
+            output.flush()?;
+        }
+        Command::Json => {
+            // Like the library default, tokens that can't be resolved don't
+            // fail the conversion
+            let file = args.open(0)?;
+            let mut out = Vec::new();
+            (&file).melt(MeltOptions::new(), &args.tokens()?, &mut out)?;
+            let header_len = SaveHeader::from_slice(&out)?.header_len();
+            let tape = TextTape::from_slice(&out[header_len..]).map_err(ImperatorError::from)?;
+            let mut output = args.output()?;
+            tape.utf8_reader()
+                .json()
+                .to_writer(&mut output)
+                .map_err(|e| CliError::Other(e.into()))?;
+            output.flush()?;
+        }
+        Command::Info => {
+            let tokens = args.tokens()?;
+            let summary = SaveSummary::from_file(args.file(0)?, &tokens)?;
+            let player = PlayerSummary::from_file(&args.open(0)?, &tokens)?;
+            let mut output = args.output()?;
+            if args.json {
+                serde_json::to_writer_pretty(&mut output, &summary_json(&summary, &player))
+                    .map_err(|e| CliError::Other(e.into()))?;
+                writeln!(output)?;
+            } else {
+                write_summary(&mut output, &summary, &player)?;
+            }
+            output.flush()?;
+        }
+        Command::Validate => {
+            let file = args.open(0)?;
+            let validated = (&file).validate(&args.tokens()?)?;
+            let mut output = args.output()?;
+            writeln!(
+                output,
+                "valid: {} {}",
+                validated.version(),
+                validated.date().game_fmt()
+            )?;
+            if let Some(checksum) = validated.checksum() {
+                writeln!(output, "checksum: {}", checksum)?;
+            }
+            output.flush()?;
+        }
+        Command::Extract => {
+            let file = args.open(0)?;
+            let mut data = Vec::new();
+            match (args.entry.as_str(), file.kind()) {
+                ("meta", _) => {
+                    file.meta()?.read_to_end(&mut data)?;
+                }
+                ("gamestate", _) => {
+                    file.gamestate()?.read_to_end(&mut data)?;
+                }
+                (name, JominiFileKind::Zip(zip)) => {
+                    let mut entry = zip.read_entry(name).map_err(ImperatorError::from)?;
+                    entry.read_to_end(&mut data)?;
+                }
+                (name, JominiFileKind::Uncompressed(_)) => {
+                    return Err(CliError::Usage(format!(
+                        "{} is not compressed and has no {} entry",
+                        args.inputs[0].display(),
+                        name
+                    )))
+                }
+            }
+
+            let mut output = args.output()?;
+            output.write_all(&data)?;
+            output.flush()?;
+        }
+        Command::Diff => {
+            let old = args.open(0)?;
+            let new = args.open(1)?;
+            let diff = SaveDiff::from_files(&old, &new, &args.tokens()?)?;
+            let mut output = args.output()?;
+            for change in diff.changes() {
+                writeln!(output, "{}", change)?;
+            }
+            output.flush()?;
+        }
+        Command::InferTokens => {
+            let binary = args.open(0)?;
+            let text = args.open(1)?;
+            let inference = TokenInference::from_files(&binary, &text, &args.token_table()?)?;
+            for conflict in inference.conflicts() {
+                eprintln!(
+                    "conflict: {:#06x} is {} but aligned with {}",
+                    conflict.id(),
+                    conflict.existing(),
+                    conflict.inferred()
+                );
+            }
+            eprintln!("inferred {} new tokens", inference.inferred().count());
+
+            let mut output = args.output()?;
+            inference.tokens().write(&mut output, TokenFormat::Text)?;
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(e.exit_code());
+        }
+    };
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let e = e.unresolved(args.unresolved.get());
+            eprintln!("error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, CliError> {
+        parse_args(args.iter().map(|x| String::from(*x)))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&["diff", "a.rome", "--tokens", "t.txt", "b.rome", "-o", "out"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.command, Command::Diff);
+        assert_eq!(
+            args.inputs,
+            vec![PathBuf::from("a.rome"), PathBuf::from("b.rome")]
+        );
+        assert_eq!(args.tokens, Some(PathBuf::from("t.txt")));
+        assert_eq!(args.output, Some(PathBuf::from("out")));
+
+        assert!(parse(&["melt", "-h"]).unwrap().is_none());
+        for invalid in [
+            &["melt"][..],
+            &["melt", "a.rome", "b.rome"],
+            &["unknown", "a.rome"],
+            &["melt", "--tokens"],
+            &["melt", "--entry", "meta", "a.rome"],
+        ] {
+            assert_eq!(parse(invalid).unwrap_err().exit_code(), 2);
+        }
+    }
+
+    #[test]
+    fn test_unresolved_exit_code() {
+        let parse_error = || CliError::Save(ImperatorErrorKind::InvalidHeader.into());
+        assert_eq!(parse_error().exit_code(), 3);
+        assert_eq!(parse_error().unresolved(None).exit_code(), 3);
+        let unresolved = parse_error().unresolved(Some(0x3000));
+        assert_eq!(unresolved.exit_code(), 4);
+        assert!(unresolved.to_string().starts_with("invalid header ("));
+        assert!(unresolved.to_string().contains("0x3000"));
+
+        let io_error = CliError::from(std::io::Error::other("closed"));
+        assert_eq!(io_error.unresolved(Some(0x3000)).exit_code(), 1);
+    }
+}
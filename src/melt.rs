@@ -1,19 +1,29 @@
-use crate::{flavor::ImperatorFlavor, ImperatorDate, ImperatorError, ImperatorErrorKind};
+use crate::{
+    fields, filter::FieldFilter, flavor::ImperatorFlavor, DateDetection, DateFormat, FieldAction,
+    ImperatorDate, ImperatorError, ImperatorErrorKind, MeltField,
+};
 use jomini::{
     binary::{self, BinaryFlavor, FailedResolveStrategy, TokenReader, TokenResolver},
-    common::PdsDate,
+    common::{PdsDate, PdsDateFormatter, RawDate},
     envelope::{SaveHeader, SaveHeaderKind},
-    TextWriterBuilder,
+    TextWriter, TextWriterBuilder,
 };
 use std::{
     collections::HashSet,
-    io::{Cursor, Read, Write},
+    fmt::Write as _,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    time::{Duration, Instant},
 };
 
 /// Output from melting a binary save to plaintext
 #[derive(Debug, Default)]
 pub struct MeltedDocument {
     unknown_tokens: HashSet<u16>,
+    token_counts: TokenCounts,
+    dates_detected: u64,
+    unresolved_fields: Vec<UnresolvedField>,
+    output_len: u64,
+    elapsed: Option<Duration>,
 }
 
 impl MeltedDocument {
@@ -25,12 +35,238 @@ impl MeltedDocument {
     pub fn unknown_tokens(&self) -> &HashSet<u16> {
         &self.unknown_tokens
     }
+
+    /// The number of each kind of binary token read while melting. Tokens
+    /// within containers that were skipped wholesale are not counted.
+    pub fn token_counts(&self) -> &TokenCounts {
+        &self.token_counts
+    }
+
+    /// The number of integers that were written out as dates
+    pub fn dates_detected(&self) -> u64 {
+        self.dates_detected
+    }
+
+    /// Every occurrence of a token that the resolver did not recognize, in
+    /// document order
+    pub fn unresolved_fields(&self) -> &[UnresolvedField] {
+        &self.unresolved_fields
+    }
+
+    /// Fields that were omitted from the output as their key could not be
+    /// resolved and [`FailedResolveStrategy::Ignore`] was in effect
+    pub fn skipped_fields(&self) -> impl Iterator<Item = &UnresolvedField> {
+        self.unresolved_fields.iter().filter(|x| x.is_skipped())
+    }
+
+    /// The number of bytes written to the output, including the header
+    pub fn output_len(&self) -> u64 {
+        self.output_len
+    }
+
+    /// How long the melt took. Not available on platforms without a monotonic
+    /// clock (wasm32-unknown-unknown).
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    /// Fold in the statistics of a document melted after this one
+    fn append(&mut self, other: MeltedDocument) {
+        self.unknown_tokens.extend(other.unknown_tokens);
+        self.token_counts.add(&other.token_counts);
+        self.dates_detected += other.dates_detected;
+        self.unresolved_fields.extend(other.unresolved_fields);
+    }
+}
+
+/// Tally of binary tokens by kind
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCounts {
+    pub open: u64,
+    pub close: u64,
+    pub equal: u64,
+    pub u32: u64,
+    pub u64: u64,
+    pub i32: u64,
+    pub bool: u64,
+    pub quoted: u64,
+    pub unquoted: u64,
+    pub f32: u64,
+    pub f64: u64,
+    pub rgb: u64,
+    pub i64: u64,
+    pub lookup: u64,
+    pub id: u64,
+}
+
+impl TokenCounts {
+    /// The total number of tokens
+    pub fn total(&self) -> u64 {
+        self.open
+            + self.close
+            + self.equal
+            + self.u32
+            + self.u64
+            + self.i32
+            + self.bool
+            + self.quoted
+            + self.unquoted
+            + self.f32
+            + self.f64
+            + self.rgb
+            + self.i64
+            + self.lookup
+            + self.id
+    }
+
+    fn add(&mut self, other: &TokenCounts) {
+        self.open += other.open;
+        self.close += other.close;
+        self.equal += other.equal;
+        self.u32 += other.u32;
+        self.u64 += other.u64;
+        self.i32 += other.i32;
+        self.bool += other.bool;
+        self.quoted += other.quoted;
+        self.unquoted += other.unquoted;
+        self.f32 += other.f32;
+        self.f64 += other.f64;
+        self.rgb += other.rgb;
+        self.i64 += other.i64;
+        self.lookup += other.lookup;
+        self.id += other.id;
+    }
+
+    fn record(&mut self, token: &binary::Token) {
+        let count = match token {
+            binary::Token::Open => &mut self.open,
+            binary::Token::Close => &mut self.close,
+            binary::Token::Equal => &mut self.equal,
+            binary::Token::U32(_) => &mut self.u32,
+            binary::Token::U64(_) => &mut self.u64,
+            binary::Token::I32(_) => &mut self.i32,
+            binary::Token::Bool(_) => &mut self.bool,
+            binary::Token::Quoted(_) => &mut self.quoted,
+            binary::Token::Unquoted(_) => &mut self.unquoted,
+            binary::Token::F32(_) => &mut self.f32,
+            binary::Token::F64(_) => &mut self.f64,
+            binary::Token::Rgb(_) => &mut self.rgb,
+            binary::Token::I64(_) => &mut self.i64,
+            binary::Token::Lookup(_) => &mut self.lookup,
+            binary::Token::Id(_) => &mut self.id,
+        };
+        *count += 1;
+    }
+}
+
+/// A binary token that the resolver did not recognize and where in the
+/// document it was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedField {
+    token_id: u16,
+    path: String,
+    usage: TokenUsage,
+    skipped: bool,
+}
+
+impl UnresolvedField {
+    /// The unresolved token
+    pub fn token_id(&self) -> u16 {
+        self.token_id
+    }
+
+    /// Dot delimited path of keys leading to the token. Unresolved keys are
+    /// denoted with a final `<unknown>` segment (eg:
+    /// `country.country_database.12.<unknown>`) while unresolved values
+    /// take on the path of the field they are assigned to. The keys leading
+    /// to the token are only recorded when
+    /// [`MeltOptions::unresolved_paths`] is enabled.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Whether the token was used as a key or a value
+    pub fn usage(&self) -> TokenUsage {
+        self.usage
+    }
+
+    /// Whether the field was omitted from the melted output
+    pub fn is_skipped(&self) -> bool {
+        self.skipped
+    }
+
+    fn key(mut self, value: ValueKind) -> Self {
+        self.path = child_path(&self.path, "<unknown>");
+        self.usage = TokenUsage::Key(value);
+        self
+    }
 }
 
+/// How an unresolved token was used within the document
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenUsage {
+    /// The token is a key that is assigned a value of the given kind
+    Key(ValueKind),
+
+    /// The token is a value, either of a field or an array element
+    Value,
+}
+
+/// The kind of value that follows a key in the binary format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// An object or array
+    Container,
+    Bool,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    Quoted,
+    Unquoted,
+    Rgb,
+    /// A token that is resolved to a string
+    Token,
+    Lookup,
+}
+
+impl ValueKind {
+    fn from_token(token: &binary::Token) -> Option<Self> {
+        match token {
+            binary::Token::Open => Some(ValueKind::Container),
+            binary::Token::Close | binary::Token::Equal => None,
+            binary::Token::U32(_) => Some(ValueKind::U32),
+            binary::Token::U64(_) => Some(ValueKind::U64),
+            binary::Token::I32(_) => Some(ValueKind::I32),
+            binary::Token::Bool(_) => Some(ValueKind::Bool),
+            binary::Token::Quoted(_) => Some(ValueKind::Quoted),
+            binary::Token::Unquoted(_) => Some(ValueKind::Unquoted),
+            binary::Token::F32(_) => Some(ValueKind::F32),
+            binary::Token::F64(_) => Some(ValueKind::F64),
+            binary::Token::Rgb(_) => Some(ValueKind::Rgb),
+            binary::Token::I64(_) => Some(ValueKind::I64),
+            binary::Token::Lookup(_) => Some(ValueKind::Lookup),
+            binary::Token::Id(_) => Some(ValueKind::Token),
+        }
+    }
+}
+
+/// Options that control how a binary save is melted. Options are equal when
+/// their settings match and they share the same field filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MeltOptions {
     verbatim: bool,
     on_failed_resolve: FailedResolveStrategy,
+    date_detection: DateDetection,
+    indent_char: u8,
+    indent_width: u8,
+    float_precision: Option<usize>,
+    date_format: DateFormat,
+    compact: bool,
+    field_filter: Option<FieldFilter>,
+    unresolved_paths: bool,
 }
 
 impl Default for MeltOptions {
@@ -44,9 +280,19 @@ impl MeltOptions {
         Self {
             verbatim: false,
             on_failed_resolve: FailedResolveStrategy::Ignore,
+            date_detection: DateDetection::Heuristic,
+            indent_char: b'\t',
+            indent_width: 1,
+            float_precision: None,
+            date_format: DateFormat::DotShort,
+            compact: false,
+            field_filter: None,
+            unresolved_paths: false,
         }
     }
 
+    /// When true, fields that are stripped by default (`is_ironman`) are
+    /// written out
     pub fn verbatim(self, verbatim: bool) -> Self {
         MeltOptions { verbatim, ..self }
     }
@@ -57,6 +303,108 @@ impl MeltOptions {
             ..self
         }
     }
+
+    /// Controls how integers are determined to be dates. Use
+    /// [`DateDetection::Schema`] to consult a [`FieldSchema`](crate::FieldSchema)
+    /// of known date and number fields.
+    pub fn date_detection(self, date_detection: DateDetection) -> Self {
+        MeltOptions {
+            date_detection,
+            ..self
+        }
+    }
+
+    /// The character used to indent nested containers. Defaults to a tab.
+    pub fn indent_char(self, indent_char: u8) -> Self {
+        MeltOptions {
+            indent_char,
+            ..self
+        }
+    }
+
+    /// The number of indent characters written per level of nesting.
+    /// Defaults to 1.
+    pub fn indent_width(self, indent_width: u8) -> Self {
+        MeltOptions {
+            indent_width,
+            ..self
+        }
+    }
+
+    /// The number of digits written after the decimal point for floats. When
+    /// `None` (the default), floats are written with the shortest
+    /// representation that roundtrips.
+    pub fn float_precision(self, float_precision: Option<usize>) -> Self {
+        MeltOptions {
+            float_precision,
+            ..self
+        }
+    }
+
+    /// How dates are written. Defaults to the game's own format
+    /// ([`DateFormat::DotShort`]).
+    pub fn date_format(self, date_format: DateFormat) -> Self {
+        MeltOptions {
+            date_format,
+            ..self
+        }
+    }
+
+    /// Write the metadata and the gamestate each on a single line, separating
+    /// fields with a space instead of a newline and indentation.
+    pub fn compact(self, compact: bool) -> Self {
+        MeltOptions { compact, ..self }
+    }
+
+    /// Decide, per field, whether it should be kept, dropped, renamed, or
+    /// have its value redacted. Useful for stripping player names or other
+    /// sensitive data before sharing a save.
+    ///
+    /// The filter is consulted for every resolved key before the default
+    /// stripping of `is_ironman`, which only applies when the filter keeps
+    /// the field.
+    ///
+    /// ```
+    /// use imperator_save::{FieldAction, MeltOptions};
+    /// let options = MeltOptions::new().field_filter(|field| match field.key() {
+    ///     "meta_player_name" => FieldAction::Redact(String::from("anonymous")),
+    ///     "checksum" => FieldAction::Drop,
+    ///     _ => FieldAction::Keep,
+    /// });
+    /// ```
+    pub fn field_filter<F>(self, filter: F) -> Self
+    where
+        F: Fn(&MeltField) -> FieldAction + Send + Sync + 'static,
+    {
+        MeltOptions {
+            field_filter: Some(FieldFilter::new(filter)),
+            ..self
+        }
+    }
+
+    /// Record the path of keys leading to each unresolved token in
+    /// [`UnresolvedField::path`]. Tracking the path adds work to every token
+    /// melted, so it is off by default and the path only denotes whether
+    /// the token was an unknown key. Paths are always tracked when a
+    /// [`field_filter`](MeltOptions::field_filter) is set.
+    pub fn unresolved_paths(self, unresolved_paths: bool) -> Self {
+        MeltOptions {
+            unresolved_paths,
+            ..self
+        }
+    }
+
+    fn field_action(&self, parent: &str, key: &str) -> FieldAction {
+        let action = match &self.field_filter {
+            Some(filter) => filter.action(&MeltField::new(parent, key)),
+            None => FieldAction::Keep,
+        };
+
+        match action {
+            FieldAction::Keep if !self.verbatim && key == "is_ironman" => FieldAction::Drop,
+            action => action,
+        }
+    }
 }
 
 fn update_header(data: &mut [u8], mut header: SaveHeader) {
@@ -65,9 +413,71 @@ fn update_header(data: &mut [u8], mut header: SaveHeader) {
     let _ = header.write(&mut data[..header.header_len()]);
 }
 
+/// Capacity for the buffered metadata. The melted metadata is typically
+/// about twice the size of its binary form, but the declared length is
+/// untrusted, so the hint is bounded.
+fn metadata_capacity(header: &SaveHeader) -> usize {
+    const MAX_CAPACITY: u64 = 16 << 20;
+    header.metadata_len().saturating_mul(2).min(MAX_CAPACITY) as usize
+}
+
+fn start_timer() -> Option<Instant> {
+    if cfg!(all(target_family = "wasm", target_os = "unknown")) {
+        None
+    } else {
+        Some(Instant::now())
+    }
+}
+
+/// Writer adapter that keeps track of how many bytes have been written
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        CountingWriter { inner, count: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(crate) fn melt_text<Reader, Writer>(
+    header: &SaveHeader,
+    mut input: Reader,
+    output: Writer,
+) -> Result<MeltedDocument, ImperatorError>
+where
+    Reader: Read,
+    Writer: Write,
+{
+    let timer = start_timer();
+    let mut output = CountingWriter::new(output);
+    let mut new_header = header.clone();
+    new_header.set_kind(SaveHeaderKind::Text);
+    new_header.write(&mut output)?;
+    std::io::copy(&mut input, &mut output)?;
+    Ok(MeltedDocument {
+        output_len: output.count,
+        elapsed: timer.map(|x| x.elapsed()),
+        ..MeltedDocument::default()
+    })
+}
+
 pub(crate) fn melt<Reader, Writer, Resolver>(
     input: Reader,
-    mut output: Writer,
+    output: Writer,
     resolver: Resolver,
     options: MeltOptions,
     header: SaveHeader,
@@ -77,9 +487,11 @@ where
     Writer: Write,
     Resolver: TokenResolver,
 {
-    let mut unknown_tokens = HashSet::new();
+    let timer = start_timer();
+    let mut doc = MeltedDocument::new();
+    let mut output = CountingWriter::new(output);
     let mut reader = TokenReader::new(input);
-    let out = Vec::with_capacity((header.metadata_len() * 2) as usize);
+    let out = Vec::with_capacity(metadata_capacity(&header));
     let mut cursor = Cursor::new(out);
     let _ = header.write(&mut cursor);
 
@@ -87,10 +499,116 @@ where
         &mut reader,
         &mut cursor,
         &resolver,
-        options,
+        &options,
         Some(&header),
         false,
-        &mut unknown_tokens,
+        &mut doc,
+    )?;
+
+    let mut metadata = cursor.into_inner();
+    update_header(&mut metadata, header);
+    output.write_all(&metadata)?;
+    output.write_all(&b"\n"[..])?;
+
+    melt_gamestate(
+        &mut reader,
+        &mut output,
+        &resolver,
+        &options,
+        melter_return,
+        &mut doc,
+    )?;
+
+    doc.output_len = output.count;
+    doc.elapsed = timer.map(|x| x.elapsed());
+    Ok(doc)
+}
+
+/// Melt without buffering the metadata in memory.
+///
+/// The header is written with a placeholder metadata length, the metadata is
+/// streamed directly to the output, and then the writer seeks back to patch
+/// the header before continuing on with the gamestate.
+pub(crate) fn melt_seekable<Reader, Writer, Resolver>(
+    input: Reader,
+    mut output: Writer,
+    resolver: Resolver,
+    options: MeltOptions,
+    mut header: SaveHeader,
+) -> Result<MeltedDocument, ImperatorError>
+where
+    Reader: Read,
+    Writer: Write + Seek,
+    Resolver: TokenResolver,
+{
+    let timer = start_timer();
+    let mut doc = MeltedDocument::new();
+    let mut reader = TokenReader::new(input);
+    let start = output.stream_position()?;
+    header.set_kind(SaveHeaderKind::Text);
+    header.write(&mut output)?;
+
+    let melter_return = melt_inner(
+        &mut reader,
+        &mut output,
+        &resolver,
+        &options,
+        Some(&header),
+        false,
+        &mut doc,
+    )?;
+    output.write_all(&b"\n"[..])?;
+
+    let end = output.stream_position()?;
+    header.set_metadata_len(end - start - header.header_len() as u64);
+    output.seek(SeekFrom::Start(start))?;
+    header.write(&mut output)?;
+    output.seek(SeekFrom::Start(end))?;
+
+    melt_gamestate(
+        &mut reader,
+        &mut output,
+        &resolver,
+        &options,
+        melter_return,
+        &mut doc,
+    )?;
+
+    doc.output_len = output.stream_position()? - start;
+    doc.elapsed = timer.map(|x| x.elapsed());
+    Ok(doc)
+}
+
+/// Melt with the gamestate's top level fields split into chunks that are
+/// melted across threads. The output is identical to [`melt`].
+pub(crate) fn melt_parallel<Writer, Resolver>(
+    data: &[u8],
+    output: Writer,
+    resolver: Resolver,
+    options: MeltOptions,
+    header: SaveHeader,
+    threads: usize,
+) -> Result<MeltedDocument, ImperatorError>
+where
+    Writer: Write,
+    Resolver: TokenResolver + Sync,
+{
+    let timer = start_timer();
+    let mut doc = MeltedDocument::new();
+    let mut output = CountingWriter::new(output);
+    let mut reader = TokenReader::from_slice(data);
+    let out = Vec::with_capacity(metadata_capacity(&header));
+    let mut cursor = Cursor::new(out);
+    let _ = header.write(&mut cursor);
+
+    let melter_return = melt_inner(
+        &mut reader,
+        &mut cursor,
+        &resolver,
+        &options,
+        Some(&header),
+        false,
+        &mut doc,
     )?;
 
     let mut metadata = cursor.into_inner();
@@ -98,23 +616,114 @@ where
     output.write_all(&metadata)?;
     output.write_all(&b"\n"[..])?;
 
+    if melter_return != MelterReturn::Eof {
+        let gamestate = &data[reader.position()..];
+        let chunks = split_fields(gamestate, threads);
+        let melted = std::thread::scope(|scope| {
+            let handles = chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| {
+                    let (resolver, options) = (&resolver, &options);
+                    scope.spawn(move || {
+                        let mut out = Vec::with_capacity(chunk.len() * 2);
+                        let mut doc = MeltedDocument::new();
+                        let mut reader = TokenReader::from_slice(chunk);
+                        melt_inner(
+                            &mut reader,
+                            &mut out,
+                            resolver,
+                            options,
+                            None,
+                            i == 0,
+                            &mut doc,
+                        )?;
+                        Ok::<_, ImperatorError>((out, doc))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|x| x.join().expect("melt thread panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        // A fresh writer omits the line break that would have preceded the
+        // chunk's first field. Chunks may be empty when the field filter
+        // drops all of their fields.
+        let separator: &[u8] = if options.compact { b" " } else { b"\n" };
+        let mut written = false;
+        for result in melted {
+            let (out, chunk_doc) = result?;
+            if written && !out.is_empty() {
+                output.write_all(separator)?;
+            }
+            written |= !out.is_empty();
+            output.write_all(&out)?;
+            doc.append(chunk_doc);
+        }
+        output.write_all(&b"\n"[..])?;
+    }
+
+    doc.output_len = output.count;
+    doc.elapsed = timer.map(|x| x.elapsed());
+    Ok(doc)
+}
+
+/// Split the gamestate, which starts immediately after the key of its first
+/// field, into at most `chunks` pieces of similar size. Splits only occur
+/// between top level `key=value` fields. Once anything else is encountered,
+/// the rest of the data is left in the final piece.
+fn split_fields(data: &[u8], chunks: usize) -> Vec<&[u8]> {
+    let starts = fields::binary_fields(data, true)
+        .map_while(Result::ok)
+        .take_while(|x| x.aligned)
+        .map(|x| x.start);
+
+    let target = data.len() / chunks.max(1);
+    let mut result = Vec::with_capacity(chunks);
+    let mut last = 0;
+    for start in starts {
+        if start - last >= target.max(1) && result.len() + 1 < chunks {
+            result.push(&data[last..start]);
+            last = start;
+        }
+    }
+    result.push(&data[last..]);
+    result
+}
+
+fn melt_gamestate<Reader, Writer, Resolver>(
+    reader: &mut TokenReader<Reader>,
+    mut output: Writer,
+    resolver: Resolver,
+    options: &MeltOptions,
+    melter_return: MelterReturn,
+    doc: &mut MeltedDocument,
+) -> Result<(), ImperatorError>
+where
+    Reader: Read,
+    Writer: Write,
+    Resolver: TokenResolver,
+{
     if melter_return != MelterReturn::Eof {
         melt_inner(
-            &mut reader,
+            reader,
             &mut output,
-            &resolver,
+            resolver,
             options,
             None,
             matches!(melter_return, MelterReturn::StartOfGamestateField),
-            &mut unknown_tokens,
+            doc,
         )?;
         output.write_all(&b"\n"[..])?;
     }
 
-    Ok(MeltedDocument { unknown_tokens })
+    Ok(())
 }
 
-const START_OF_GAMESTATE_FIELD: &[u8] = b"speed";
+const START_OF_GAMESTATE_FIELD: &str = "speed";
 
 #[derive(PartialEq)]
 enum MelterReturn {
@@ -122,14 +731,258 @@ enum MelterReturn {
     StartOfGamestateField,
 }
 
+/// The most recent scalar seen by the melter, which becomes a key if it is
+/// followed by an equal token.
+#[derive(Debug, Default, Clone, Copy)]
+enum LastScalar {
+    #[default]
+    None,
+    Token(u16),
+    Signed(i64),
+    Unsigned(u64),
+    Text,
+}
+
+/// Tracks the dot delimited path of keys leading to the current position in
+/// the melted document. Array elements are identified by their index. When
+/// disabled, the path stays empty and tokens are not inspected.
+#[derive(Debug)]
+struct KeyPath {
+    enabled: bool,
+    path: String,
+    marks: Vec<usize>,
+    indices: Vec<u32>,
+    text: Vec<u8>,
+    last: LastScalar,
+    keyed: bool,
+}
+
+impl KeyPath {
+    fn new(enabled: bool) -> Self {
+        KeyPath {
+            enabled,
+            path: String::new(),
+            marks: Vec::new(),
+            indices: vec![0],
+            text: Vec::new(),
+            last: LastScalar::None,
+            keyed: false,
+        }
+    }
+
+    fn scalar(&mut self, scalar: LastScalar) {
+        if !self.enabled {
+            return;
+        }
+
+        self.last = scalar;
+        self.keyed = false;
+    }
+
+    fn text(&mut self, data: &[u8]) {
+        if !self.enabled {
+            return;
+        }
+
+        self.text.clear();
+        self.text.extend_from_slice(data);
+        self.scalar(LastScalar::Text);
+    }
+
+    fn equal(&mut self) {
+        self.keyed = self.enabled;
+    }
+
+    fn open<R: TokenResolver>(&mut self, resolver: &R) {
+        if !self.enabled {
+            return;
+        }
+
+        self.marks.push(self.path.len());
+        if !self.path.is_empty() {
+            self.path.push('.');
+        }
+
+        if self.keyed {
+            self.push_last(resolver);
+        } else if let Some(index) = self.indices.last_mut() {
+            let _ = write!(self.path, "{}", index);
+            *index += 1;
+        }
+
+        self.indices.push(0);
+        self.scalar(LastScalar::None);
+    }
+
+    fn close(&mut self) {
+        if let Some(mark) = self.marks.pop() {
+            self.path.truncate(mark);
+            self.indices.pop();
+        }
+        self.scalar(LastScalar::None);
+    }
+
+    fn push_last<R: TokenResolver>(&mut self, resolver: &R) {
+        let mut path = std::mem::take(&mut self.path);
+        self.write_last(&mut path, resolver);
+        self.path = path;
+    }
+
+    fn write_last<R: TokenResolver>(&self, out: &mut String, resolver: &R) {
+        match self.last {
+            LastScalar::None => {}
+            LastScalar::Token(id) => match resolver.resolve(id) {
+                Some(key) => out.push_str(key),
+                None => out.push_str("<unknown>"),
+            },
+            LastScalar::Signed(x) => {
+                let _ = write!(out, "{}", x);
+            }
+            LastScalar::Unsigned(x) => {
+                let _ = write!(out, "{}", x);
+            }
+            LastScalar::Text => out.push_str(&String::from_utf8_lossy(&self.text)),
+        }
+    }
+
+    /// The path of the field that the next value will be assigned to. For
+    /// array elements, this is the path of the array.
+    fn value_path<R: TokenResolver>(&self, resolver: &R) -> String {
+        if self.keyed {
+            let mut key = String::new();
+            self.write_last(&mut key, resolver);
+            child_path(&self.path, &key)
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+fn child_path(parent: &str, segment: &str) -> String {
+    if parent.is_empty() {
+        String::from(segment)
+    } else {
+        format!("{}.{}", parent, segment)
+    }
+}
+
+/// Writer adapter that, when enabled, replaces line breaks that occur outside
+/// of quoted strings with a space
+struct CompactWriter<W> {
+    inner: W,
+    enabled: bool,
+    in_quote: bool,
+    escaped: bool,
+}
+
+impl<W> CompactWriter<W> {
+    fn new(inner: W, enabled: bool) -> Self {
+        CompactWriter {
+            inner,
+            enabled,
+            in_quote: false,
+            escaped: false,
+        }
+    }
+}
+
+impl<W: Write> Write for CompactWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if !self.enabled {
+            return self.inner.write(buf);
+        }
+
+        let mut start = 0;
+        for (i, &b) in buf.iter().enumerate() {
+            if self.escaped {
+                self.escaped = false;
+            } else if self.in_quote && b == b'\\' {
+                self.escaped = true;
+            } else if b == b'"' {
+                self.in_quote = !self.in_quote;
+            } else if b == b'\n' && !self.in_quote {
+                self.inner.write_all(&buf[start..i])?;
+                self.inner.write_all(b" ")?;
+                start = i + 1;
+            }
+        }
+
+        self.inner.write_all(&buf[start..])?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Consume the value of a field whose key was just read, returning the kind
+/// of value that was skipped
+fn skip_value<R: Read>(
+    reader: &mut TokenReader<R>,
+    doc: &mut MeltedDocument,
+) -> Result<ValueKind, ImperatorError> {
+    let mut next = reader.read()?;
+    doc.token_counts.record(&next);
+    if matches!(next, binary::Token::Equal) {
+        next = reader.read()?;
+        doc.token_counts.record(&next);
+    }
+
+    let value = ValueKind::from_token(&next).unwrap_or(ValueKind::Token);
+    if matches!(next, binary::Token::Open) {
+        reader.skip_container()?;
+    }
+
+    Ok(value)
+}
+
+/// Write the key of a field according to the action decided for it. Returns
+/// false if the field's value was consumed as the field was dropped or
+/// redacted.
+fn write_key<R: Read, W: Write>(
+    wtr: &mut TextWriter<W>,
+    reader: &mut TokenReader<R>,
+    path: &mut KeyPath,
+    doc: &mut MeltedDocument,
+    key: &str,
+    action: FieldAction,
+) -> Result<bool, ImperatorError> {
+    match action {
+        FieldAction::Keep => wtr.write_unquoted(key.as_bytes())?,
+        FieldAction::Rename(key) => wtr.write_unquoted(key.as_bytes())?,
+        FieldAction::Drop => {
+            skip_value(reader, doc)?;
+            return Ok(false);
+        }
+        FieldAction::Redact(value) => {
+            skip_value(reader, doc)?;
+            wtr.write_unquoted(key.as_bytes())?;
+            wtr.write_operator(jomini::text::Operator::Equal)?;
+            wtr.write_quoted(value.as_bytes())?;
+            path.scalar(LastScalar::None);
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// An unresolved token whose usage will be determined by the tokens that
+/// follow it
+struct PendingField {
+    field: UnresolvedField,
+    saw_equal: bool,
+}
+
 fn melt_inner<Reader, Writer, Resolver>(
     reader: &mut TokenReader<Reader>,
     output: Writer,
     resolver: Resolver,
-    options: MeltOptions,
+    options: &MeltOptions,
     header: Option<&SaveHeader>,
     write_prefix: bool,
-    unknown_tokens: &mut HashSet<u16>,
+    doc: &mut MeltedDocument,
 ) -> Result<MelterReturn, ImperatorError>
 where
     Reader: Read,
@@ -137,19 +990,62 @@ where
     Resolver: TokenResolver,
 {
     let flavor = ImperatorFlavor;
+    let indent_width = if options.compact {
+        0
+    } else {
+        options.indent_width
+    };
     let mut wtr = TextWriterBuilder::new()
-        .indent_char(b'\t')
-        .indent_factor(1)
-        .from_writer(output);
+        .indent_char(options.indent_char)
+        .indent_factor(indent_width)
+        .from_writer(CompactWriter::new(output, options.compact));
 
+    let mut path = KeyPath::new(options.unresolved_paths || options.field_filter.is_some());
+    // The resolved key of the value about to be written and the keys of the
+    // containers leading to it. Used to look up fields in the schema.
+    let mut field: Option<&str> = None;
+    let mut last_key: Option<&str> = None;
+    let mut containers: Vec<Option<&str>> = Vec::new();
+
+    // The key of the first gamestate field was consumed while melting the
+    // metadata, and is filtered like any other key
     if write_prefix {
-        wtr.write_unquoted(START_OF_GAMESTATE_FIELD)?;
+        path.text(START_OF_GAMESTATE_FIELD.as_bytes());
+        let action = options.field_action(&path.path, START_OF_GAMESTATE_FIELD);
+        if write_key(
+            &mut wtr,
+            reader,
+            &mut path,
+            doc,
+            START_OF_GAMESTATE_FIELD,
+            action,
+        )? {
+            last_key = Some(START_OF_GAMESTATE_FIELD);
+        }
     }
 
-    let mut known_number = false;
     let mut quoted_buffer_enabled = false;
     let mut quoted_buffer: Vec<u8> = Vec::new();
+    let mut pending: Option<PendingField> = None;
+
+    // A resolved token at the start of a container, which is only known to
+    // be a key, and subject to the field filter, once an equal token follows
+    let mut pending_key: Option<&str> = None;
     while let Some(token) = reader.next()? {
+        doc.token_counts.record(&token);
+        if let Some(mut field) = pending.take() {
+            if matches!(token, binary::Token::Equal) && !field.saw_equal {
+                field.saw_equal = true;
+                pending = Some(field);
+            } else {
+                let value = ValueKind::from_token(&token).filter(|_| field.saw_equal);
+                doc.unresolved_fields.push(match value {
+                    Some(value) => field.field.key(value),
+                    None => field.field,
+                });
+            }
+        }
+
         if quoted_buffer_enabled {
             if matches!(token, binary::Token::Equal) {
                 wtr.write_unquoted(&quoted_buffer)?;
@@ -160,20 +1056,61 @@ where
             quoted_buffer_enabled = false;
         }
 
+        if let Some(key) = pending_key.take() {
+            if matches!(token, binary::Token::Equal) {
+                let action = options.field_action(&path.path, key);
+                if write_key(&mut wtr, reader, &mut path, doc, key, action)? {
+                    path.equal();
+                    field = Some(key);
+                    wtr.write_operator(jomini::text::Operator::Equal)?;
+                }
+                continue;
+            } else {
+                wtr.write_unquoted(key.as_bytes())?;
+            }
+        }
+
         match token {
-            jomini::binary::Token::Open => wtr.write_start()?,
-            jomini::binary::Token::Close => wtr.write_end()?,
+            jomini::binary::Token::Open => {
+                path.open(&resolver);
+                containers.push(field.take());
+                last_key = None;
+                wtr.write_start()?
+            }
+            jomini::binary::Token::Close => {
+                path.close();
+                containers.pop();
+                field = None;
+                last_key = None;
+                wtr.write_end()?
+            }
             jomini::binary::Token::I32(x) => {
-                if known_number {
-                    wtr.write_i32(x)?;
-                    known_number = false;
-                } else if let Some(date) = ImperatorDate::from_binary_heuristic(x) {
-                    wtr.write_date(date.game_fmt())?;
+                path.scalar(LastScalar::Signed(i64::from(x)));
+                let key = field.take();
+                last_key = None;
+                let date = match &options.date_detection {
+                    DateDetection::Heuristic if key == Some("seed") => None,
+                    DateDetection::Heuristic => ImperatorDate::from_binary_heuristic(x),
+                    DateDetection::Schema(schema) => {
+                        // Array elements are described by the key of the array
+                        match key.or_else(|| containers.last().copied().flatten()) {
+                            Some(key) if schema.is_number(key) => None,
+                            Some(key) if schema.is_date(key) => ImperatorDate::from_binary(x),
+                            _ => ImperatorDate::from_binary_heuristic(x),
+                        }
+                    }
+                };
+
+                if let Some(date) = date {
+                    doc.dates_detected += 1;
+                    let raw = RawDate::from_ymdh(date.year(), date.month(), date.day(), 0);
+                    wtr.write_date(PdsDateFormatter::new(raw, options.date_format))?;
                 } else {
                     wtr.write_i32(x)?;
                 }
             }
             jomini::binary::Token::Quoted(x) => {
+                path.text(x.as_bytes());
                 if wtr.at_unknown_start() {
                     quoted_buffer_enabled = true;
                     quoted_buffer.extend_from_slice(x.as_bytes());
@@ -184,57 +1121,118 @@ where
                 }
             }
             jomini::binary::Token::Unquoted(x) => {
+                path.text(x.as_bytes());
                 wtr.write_unquoted(x.as_bytes())?;
             }
-            jomini::binary::Token::F32(x) => wtr.write_f32(flavor.visit_f32(x))?,
-            jomini::binary::Token::F64(x) => wtr.write_f64(flavor.visit_f64(x))?,
+            jomini::binary::Token::F32(x) => {
+                path.scalar(LastScalar::None);
+                let x = flavor.visit_f32(x);
+                match options.float_precision {
+                    Some(precision) => wtr.write_f32_precision(x, precision)?,
+                    None => wtr.write_f32(x)?,
+                }
+            }
+            jomini::binary::Token::F64(x) => {
+                path.scalar(LastScalar::None);
+                let x = flavor.visit_f64(x);
+                match options.float_precision {
+                    Some(precision) => wtr.write_f64_precision(x, precision)?,
+                    None => wtr.write_f64(x)?,
+                }
+            }
             jomini::binary::Token::Id(x) => match resolver.resolve(x) {
                 Some(id) => {
-                    if !options.verbatim && id == "is_ironman" && wtr.expecting_key() {
-                        let mut next = reader.read()?;
-                        if matches!(next, binary::Token::Equal) {
-                            next = reader.read()?;
-                        }
-
-                        if matches!(next, binary::Token::Open) {
-                            reader.skip_container()?;
-                        }
-                        continue;
-                    }
-
-                    if id.as_bytes() == START_OF_GAMESTATE_FIELD && header.is_some() {
+                    if id == START_OF_GAMESTATE_FIELD && header.is_some() {
                         return Ok(MelterReturn::StartOfGamestateField);
                     }
 
-                    known_number = id == "seed";
-                    wtr.write_unquoted(id.as_bytes())?;
+                    path.scalar(LastScalar::Token(x));
+                    field = None;
+                    last_key = None;
+                    if wtr.at_unknown_start() {
+                        // Could be a key or the first element of an array,
+                        // the next token will tell us which.
+                        pending_key = Some(id);
+                    } else {
+                        let action = if wtr.expecting_key() {
+                            options.field_action(&path.path, id)
+                        } else {
+                            FieldAction::Keep
+                        };
+
+                        if write_key(&mut wtr, reader, &mut path, doc, id, action)? {
+                            last_key = Some(id);
+                        }
+                    }
                 }
                 None => match options.on_failed_resolve {
                     FailedResolveStrategy::Error => {
                         return Err(ImperatorErrorKind::UnknownToken { token_id: x as u32 }.into());
                     }
                     FailedResolveStrategy::Ignore if wtr.expecting_key() => {
-                        let mut next = reader.read()?;
-                        if matches!(next, binary::Token::Equal) {
-                            next = reader.read()?;
-                        }
+                        let field = UnresolvedField {
+                            token_id: x,
+                            path: path.path.clone(),
+                            usage: TokenUsage::Value,
+                            skipped: true,
+                        };
 
-                        if matches!(next, binary::Token::Open) {
-                            reader.skip_container()?;
-                        }
+                        let value = skip_value(reader, doc)?;
+                        doc.unresolved_fields.push(field.key(value));
                     }
                     _ => {
-                        unknown_tokens.insert(x);
+                        if wtr.expecting_key() || wtr.at_unknown_start() {
+                            // Could be a key or the first element of an
+                            // array, the next token will tell us which.
+                            pending = Some(PendingField {
+                                field: UnresolvedField {
+                                    token_id: x,
+                                    path: path.path.clone(),
+                                    usage: TokenUsage::Value,
+                                    skipped: false,
+                                },
+                                saw_equal: false,
+                            });
+                        } else {
+                            doc.unresolved_fields.push(UnresolvedField {
+                                token_id: x,
+                                path: path.value_path(&resolver),
+                                usage: TokenUsage::Value,
+                                skipped: false,
+                            });
+                        }
+
+                        path.scalar(LastScalar::Token(x));
+                        doc.unknown_tokens.insert(x);
                         write!(wtr, "__unknown_0x{:x}", x)?;
                     }
                 },
             },
-            jomini::binary::Token::Equal => wtr.write_operator(jomini::text::Operator::Equal)?,
-            jomini::binary::Token::U32(x) => wtr.write_u32(x)?,
-            jomini::binary::Token::U64(x) => wtr.write_u64(x)?,
-            jomini::binary::Token::Bool(x) => wtr.write_bool(x)?,
-            jomini::binary::Token::Rgb(x) => wtr.write_rgb(&x)?,
-            jomini::binary::Token::I64(x) => wtr.write_i64(x)?,
+            jomini::binary::Token::Equal => {
+                path.equal();
+                field = last_key.take();
+                wtr.write_operator(jomini::text::Operator::Equal)?
+            }
+            jomini::binary::Token::U32(x) => {
+                path.scalar(LastScalar::Unsigned(u64::from(x)));
+                wtr.write_u32(x)?
+            }
+            jomini::binary::Token::U64(x) => {
+                path.scalar(LastScalar::Unsigned(x));
+                wtr.write_u64(x)?
+            }
+            jomini::binary::Token::Bool(x) => {
+                path.scalar(LastScalar::None);
+                wtr.write_bool(x)?
+            }
+            jomini::binary::Token::Rgb(x) => {
+                path.scalar(LastScalar::None);
+                wtr.write_rgb(&x)?
+            }
+            jomini::binary::Token::I64(x) => {
+                path.scalar(LastScalar::Signed(x));
+                wtr.write_i64(x)?
+            }
             jomini::binary::Token::Lookup(x) => {
                 return Err(ImperatorError::new(ImperatorErrorKind::InvalidSyntax(
                     format!("encountered lookup token with id {x:#x} which is unsupported"),
@@ -243,5 +1241,13 @@ where
         }
     }
 
+    if let Some(field) = pending {
+        doc.unresolved_fields.push(field.field);
+    }
+
+    if let Some(key) = pending_key {
+        wtr.write_unquoted(key.as_bytes())?;
+    }
+
     Ok(MelterReturn::Eof)
 }
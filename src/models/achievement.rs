@@ -0,0 +1,72 @@
+use super::Save;
+use jomini::envelope::SaveHeaderKind;
+
+/// Why a save cannot earn achievements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ineligible {
+    /// Neither of the save's ironman flags is set
+    NotIronman,
+
+    /// Ironman saves are always binary, so a plaintext save has been
+    /// converted or written in debug mode
+    TextSave { kind: SaveHeaderKind },
+
+    /// The listed mods were enabled
+    Modded { mods: Vec<String> },
+
+    /// The campaign was not started on normal difficulty
+    Difficulty { difficulty: String },
+}
+
+/// The result of checking if a save is eligible for achievements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AchievementEligibility {
+    Eligible,
+    Ineligible(Ineligible),
+}
+
+impl AchievementEligibility {
+    pub fn is_eligible(&self) -> bool {
+        matches!(self, AchievementEligibility::Eligible)
+    }
+}
+
+impl Save {
+    /// Determine if the save is eligible for achievements given the kind
+    /// recorded in the save's header. The first failed requirement is
+    /// reported.
+    ///
+    /// Either of the metadata's `ironman` or `iron` flags marks the save as
+    /// ironman, as some saves only record one of them. Enabled DLC does not
+    /// affect eligibility. Saves that do not record a difficulty are
+    /// assumed to be on normal.
+    pub fn achievement_eligibility(&self, kind: SaveHeaderKind) -> AchievementEligibility {
+        if !(self.meta.ironman || self.meta.iron) {
+            return AchievementEligibility::Ineligible(Ineligible::NotIronman);
+        }
+
+        if kind.is_text() {
+            return AchievementEligibility::Ineligible(Ineligible::TextSave { kind });
+        }
+
+        if !self.meta.enabled_mods.is_empty() {
+            return AchievementEligibility::Ineligible(Ineligible::Modded {
+                mods: self.meta.enabled_mods.clone(),
+            });
+        }
+
+        let rules = self.gamestate.game_rules.as_ref();
+        match rules.and_then(|x| x.difficulty.as_deref()) {
+            // No recorded difficulty, assumed to be normal
+            None => {}
+            Some(x) if x.eq_ignore_ascii_case("normal") => {}
+            Some(x) => {
+                return AchievementEligibility::Ineligible(Ineligible::Difficulty {
+                    difficulty: String::from(x),
+                })
+            }
+        }
+
+        AchievementEligibility::Eligible
+    }
+}
@@ -0,0 +1,12 @@
+use crate::ImperatorDate;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Character {
+    /// The id of the country the character belongs to
+    pub country: Option<u32>,
+    pub culture: Option<String>,
+    pub religion: Option<String>,
+    pub birth_date: Option<ImperatorDate>,
+}
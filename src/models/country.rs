@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Country {
+    pub tag: String,
+    #[serde(default)]
+    pub currency_data: CurrencyData,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CurrencyData {
+    pub gold: f64,
+    pub manpower: f64,
+    pub stability: f64,
+    pub tyranny: f64,
+    pub war_exhaustion: f64,
+    pub political_influence: f64,
+}
@@ -0,0 +1,70 @@
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{collections::HashMap, fmt, marker::PhantomData};
+
+/// A collection of entities keyed by their id. Entries that no longer exist
+/// are written as `none` and omitted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Database<T> {
+    pub database: HashMap<u32, T>,
+}
+
+impl<T> Default for Database<T> {
+    fn default() -> Self {
+        Database {
+            database: HashMap::new(),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Database<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper<T> {
+            #[serde(default = "HashMap::new")]
+            database: HashMap<u32, Entry<T>>,
+        }
+
+        let wrapper = Wrapper::<T>::deserialize(deserializer)?;
+        let database = wrapper
+            .database
+            .into_iter()
+            .filter_map(|(id, entry)| entry.0.map(|x| (id, x)))
+            .collect();
+        Ok(Database { database })
+    }
+}
+
+struct Entry<T>(Option<T>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Entry<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct EntryVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for EntryVisitor<T> {
+            type Value = Entry<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object or none")
+            }
+
+            fn visit_str<E: de::Error>(self, _v: &str) -> Result<Self::Value, E> {
+                Ok(Entry(None))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                T::deserialize(MapAccessDeserializer::new(map)).map(|x| Entry(Some(x)))
+            }
+        }
+
+        deserializer.deserialize_map(EntryVisitor(PhantomData))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ImperatorDate;
+use crate::{GameVersion, ImperatorDate};
 use serde::Deserialize;
 
 #[derive(Debug)]
@@ -7,22 +7,49 @@ pub struct Save {
     pub gamestate: GameState,
 }
 
+/// The metadata of a save.
+///
+/// The same model is used for every game version. Fields that not every
+/// version writes are optional or defaulted rather than interpreted based on
+/// [`Metadata::version`].
 #[derive(Debug, Deserialize)]
 pub struct Metadata {
-    pub version: String,
+    pub version: GameVersion,
     pub date: ImperatorDate,
     #[serde(default)]
     pub ironman: bool,
+    /// The name of the played country, despite the key
     pub meta_player_name: Option<String>,
     pub enabled_dlcs: Vec<String>,
     pub play_time: i32,
     #[serde(default)]
     pub iron: bool,
+    #[serde(default)]
+    pub enabled_mods: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GameState {
     pub speed: i32,
+    pub game_rules: Option<GameRules>,
+}
+
+/// A country controlled by a player
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct PlayedCountry {
+    #[serde(default, alias = "name")]
+    pub player_name: Option<String>,
+
+    /// The id of the country within the country database
+    #[serde(default)]
+    pub country: Option<u32>,
+}
+
+/// The rules selected when the campaign was started
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct GameRules {
+    #[serde(default)]
+    pub difficulty: Option<String>,
 }
 
 impl<'de> Deserialize<'de> for Save {
@@ -32,7 +59,7 @@ impl<'de> Deserialize<'de> for Save {
     {
         #[derive(Debug, Deserialize)]
         struct ImperatorFlatten {
-            pub version: String,
+            pub version: GameVersion,
             pub date: ImperatorDate,
             #[serde(default)]
             pub ironman: bool,
@@ -41,7 +68,10 @@ impl<'de> Deserialize<'de> for Save {
             pub play_time: i32,
             #[serde(default)]
             pub iron: bool,
+            #[serde(default)]
+            pub enabled_mods: Vec<String>,
             pub speed: i32,
+            pub game_rules: Option<GameRules>,
         }
 
         let result = ImperatorFlatten::deserialize(deserializer)?;
@@ -54,9 +84,11 @@ impl<'de> Deserialize<'de> for Save {
                 enabled_dlcs: result.enabled_dlcs,
                 play_time: result.play_time,
                 iron: result.iron,
+                enabled_mods: result.enabled_mods,
             },
             gamestate: GameState {
                 speed: result.speed,
+                game_rules: result.game_rules,
             },
         })
     }
@@ -1,3 +1,15 @@
+mod achievement;
+mod character;
+mod country;
+mod database;
 mod gamestate;
+mod population;
+mod province;
 
+pub use achievement::*;
+pub use character::*;
+pub use country::*;
+pub use database::*;
 pub use gamestate::*;
+pub use population::*;
+pub use province::*;
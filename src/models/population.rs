@@ -0,0 +1,11 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Pop {
+    /// The pop type (eg: `citizen`, `freemen`, `slaves`)
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub culture: Option<String>,
+    pub religion: Option<String>,
+}
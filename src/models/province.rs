@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Province {
+    /// The id of the country that owns the province
+    pub owner: Option<u32>,
+    pub culture: Option<String>,
+    pub religion: Option<String>,
+}
@@ -0,0 +1,442 @@
+use crate::{ImperatorError, ImperatorErrorKind, ImperatorFile};
+use jomini::{
+    binary::{self, TokenResolver},
+    envelope::{JominiFileKind, ReaderAt, SaveContentKind, SaveDataKind, SaveMetadataKind},
+    text, Scalar,
+};
+use std::{
+    collections::HashSet,
+    io::{Read, Write},
+    ops::Range,
+};
+
+/// Controls what is written in place of player identifying data when
+/// redacting a save.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactOptions {
+    replacement: String,
+    fields: Vec<String>,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RedactOptions {
+    pub fn new() -> Self {
+        RedactOptions {
+            replacement: String::from("Anonymous"),
+            fields: Vec::new(),
+        }
+    }
+
+    /// The name written in place of `meta_player_name`, the name of the
+    /// played country shown when browsing saves. Player names found in
+    /// `played_country` are written as this name followed by a number so
+    /// that players in multiplayer saves remain distinct. Defaults to
+    /// `Anonymous`.
+    pub fn replacement(self, replacement: impl Into<String>) -> Self {
+        RedactOptions {
+            replacement: replacement.into(),
+            ..self
+        }
+    }
+
+    /// Additionally redact the quoted values of fields with the given key,
+    /// wherever they appear in the save. Binary saves must contain the key,
+    /// as a key that is absent can't be told apart from one that the
+    /// resolver doesn't know.
+    pub fn field(mut self, key: impl Into<String>) -> Self {
+        self.fields.push(key.into());
+        self
+    }
+}
+
+pub trait ImperatorRedact {
+    /// Rewrite the save with player identifying data replaced, preserving the
+    /// save's encoding and compression so that the output is still loadable
+    /// by the game. Zip entries other than the gamestate and metadata are
+    /// copied through unchanged. Returns the number of values that were
+    /// replaced.
+    ///
+    /// Binary saves require a resolver so that the fields can be identified.
+    /// An error is returned if the resolver is empty or `meta_player_name`
+    /// or a requested [field](RedactOptions::field) could not be resolved,
+    /// rather than leaving the data in place.
+    fn redact<Resolver, Writer>(
+        &mut self,
+        options: &RedactOptions,
+        resolver: Resolver,
+        output: Writer,
+    ) -> Result<usize, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write;
+}
+
+impl ImperatorRedact for &'_ [u8] {
+    fn redact<Resolver, Writer>(
+        &mut self,
+        options: &RedactOptions,
+        resolver: Resolver,
+        output: Writer,
+    ) -> Result<usize, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write,
+    {
+        let file = ImperatorFile::from_slice(*self)?;
+        let mut entries = Vec::new();
+        if matches!(file.kind(), JominiFileKind::Zip(_)) {
+            let archive = rawzip::ZipArchive::from_slice(*self)?;
+            let mut iter = archive.entries();
+            while let Some(entry) = iter.next_entry()? {
+                entries.push(entry_name(&entry));
+            }
+        }
+
+        redact_file(&file, &entries, options, &resolver, output)
+    }
+}
+
+impl ImperatorRedact for &'_ std::fs::File {
+    fn redact<Resolver, Writer>(
+        &mut self,
+        options: &RedactOptions,
+        resolver: Resolver,
+        output: Writer,
+    ) -> Result<usize, ImperatorError>
+    where
+        Resolver: TokenResolver,
+        Writer: Write,
+    {
+        let file = ImperatorFile::from_file(self.try_clone()?)?;
+        let mut entries = Vec::new();
+        if matches!(file.kind(), JominiFileKind::Zip(_)) {
+            let mut buf = vec![0u8; rawzip::RECOMMENDED_BUFFER_SIZE];
+            let archive = rawzip::ZipArchive::from_file(self.try_clone()?, &mut buf)?;
+            let mut iter = archive.entries(&mut buf);
+            while let Some(entry) = iter.next_entry()? {
+                entries.push(entry_name(&entry));
+            }
+        }
+
+        redact_file(&file, &entries, options, &resolver, output)
+    }
+}
+
+fn entry_name(entry: &rawzip::ZipFileHeaderRecord) -> String {
+    String::from_utf8_lossy(entry.file_path().as_ref()).into_owned()
+}
+
+/// Redact a save whose zip, if any, contains the given entries. Entries
+/// other than the gamestate and metadata are copied through unchanged.
+fn redact_file<R, Resolver, Writer>(
+    file: &ImperatorFile<R>,
+    entries: &[String],
+    options: &RedactOptions,
+    resolver: &Resolver,
+    mut output: Writer,
+) -> Result<usize, ImperatorError>
+where
+    R: ReaderAt,
+    Resolver: TokenResolver,
+    Writer: Write,
+{
+    let mut redactor = Redactor::new(options, resolver);
+    let mut header = file.header().clone();
+    let is_text = header.kind().is_text();
+    if !is_text && resolver.is_empty() {
+        return Err(ImperatorErrorKind::UnresolvedRedaction {
+            key: String::from(META_PLAYER_NAME),
+        }
+        .into());
+    }
+
+    match file.kind() {
+        JominiFileKind::Uncompressed(kind) => {
+            let mut data = Vec::new();
+            match kind {
+                SaveDataKind::Text(x) => x.body().cursor().read_to_end(&mut data)?,
+                SaveDataKind::Binary(x) => x.body().cursor().read_to_end(&mut data)?,
+            };
+
+            let split = (header.metadata_len() as usize).min(data.len());
+            let (meta, gamestate) = data.split_at(split);
+            let meta = redactor.redact(meta, is_text)?;
+            let gamestate = redactor.redact(gamestate, is_text)?;
+
+            header.set_metadata_len(meta.len() as u64);
+            header.write(&mut output)?;
+            redactor.check_resolved(is_text)?;
+            output.write_all(&meta)?;
+            output.write_all(&gamestate)?;
+        }
+        JominiFileKind::Zip(zip) => {
+            let mut meta = Vec::new();
+            match zip.meta()? {
+                SaveMetadataKind::Text(mut x) => x.read_to_end(&mut meta)?,
+                SaveMetadataKind::Binary(mut x) => x.read_to_end(&mut meta)?,
+            };
+            let meta = redactor.redact(&meta, is_text)?;
+
+            let mut gamestate = Vec::new();
+            match zip.gamestate()? {
+                SaveContentKind::Text(mut x) => x.read_to_end(&mut gamestate)?,
+                SaveContentKind::Binary(mut x) => x.read_to_end(&mut gamestate)?,
+            };
+            let gamestate = redactor.redact(&gamestate, is_text)?;
+            redactor.check_resolved(is_text)?;
+
+            // Metadata is either inlined between the header and the zip
+            // or stored as its own entry within the zip
+            let meta_entry = entries.iter().any(|x| x == "meta");
+            let inlined: &[u8] = if meta_entry { &[] } else { &meta };
+            header.set_metadata_len(inlined.len() as u64);
+            header.write(&mut output)?;
+            output.write_all(inlined)?;
+
+            let offset = (header.header_len() + inlined.len()) as u64;
+            let mut archive = rawzip::ZipArchiveWriter::builder()
+                .with_offset(offset)
+                .build(&mut output);
+            for name in entries {
+                match name.as_str() {
+                    "gamestate" => write_zip_entry(&mut archive, name, &gamestate)?,
+                    "meta" => write_zip_entry(&mut archive, name, &meta)?,
+                    _ => {
+                        let mut data = Vec::new();
+                        zip.read_entry(name)?.read_to_end(&mut data)?;
+                        write_zip_entry(&mut archive, name, &data)?;
+                    }
+                }
+            }
+            archive.finish()?;
+        }
+    }
+
+    Ok(redactor.replaced)
+}
+
+fn write_zip_entry<W: Write>(
+    archive: &mut rawzip::ZipArchiveWriter<W>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), ImperatorError> {
+    let (mut entry, config) = archive
+        .new_file(name)
+        .compression_method(rawzip::CompressionMethod::Deflate)
+        .start()?;
+    let encoder = flate2::write::DeflateEncoder::new(&mut entry, flate2::Compression::default());
+    let mut writer = config.wrap(encoder);
+    writer.write_all(data)?;
+    let (encoder, descriptor) = writer.finish()?;
+    encoder.finish()?;
+    entry.finish(descriptor)?;
+    Ok(())
+}
+
+/// The field that every save has and so must be resolvable in binary saves
+const META_PLAYER_NAME: &str = "meta_player_name";
+
+/// Byte ranges of the input to be swapped out with the encoded replacement
+type Replacements = Vec<(Range<usize>, Vec<u8>)>;
+
+/// How the value of an identified field is replaced
+enum Target {
+    /// Replaced with the configured name
+    Name,
+
+    /// Replaced with the configured name and the player's number
+    Player,
+}
+
+struct Redactor<'a, R> {
+    options: &'a RedactOptions,
+    resolver: &'a R,
+    players: Vec<Vec<u8>>,
+    replaced: usize,
+    resolved: HashSet<&'a str>,
+}
+
+impl<'a, R: TokenResolver> Redactor<'a, R> {
+    fn new(options: &'a RedactOptions, resolver: &'a R) -> Self {
+        Redactor {
+            options,
+            resolver,
+            players: Vec::new(),
+            replaced: 0,
+            resolved: HashSet::new(),
+        }
+    }
+
+    /// The keys that must be resolved at least once in a binary save
+    fn required(&self) -> impl Iterator<Item = &'a str> {
+        let fields = self.options.fields.iter().map(String::as_str);
+        std::iter::once(META_PLAYER_NAME).chain(fields)
+    }
+
+    fn check_resolved(&self, is_text: bool) -> Result<(), ImperatorError> {
+        if is_text {
+            return Ok(());
+        }
+
+        match self.required().find(|x| !self.resolved.contains(x)) {
+            Some(key) => Err(ImperatorErrorKind::UnresolvedRedaction {
+                key: String::from(key),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    fn target(&self, key: &[u8], parent: Option<&[u8]>) -> Option<Target> {
+        match key {
+            b"meta_player_name" => Some(Target::Name),
+            b"player_name" => Some(Target::Player),
+            b"name" if parent == Some(b"played_country") => Some(Target::Player),
+            _ if self.options.fields.iter().any(|x| x.as_bytes() == key) => Some(Target::Player),
+            _ => None,
+        }
+    }
+
+    fn replacement(&mut self, target: Target, original: &[u8]) -> String {
+        self.replaced += 1;
+        match target {
+            Target::Name => self.options.replacement.clone(),
+            Target::Player => {
+                let index = match self.players.iter().position(|x| x == original) {
+                    Some(index) => index,
+                    None => {
+                        self.players.push(original.to_vec());
+                        self.players.len() - 1
+                    }
+                };
+                format!("{} {}", self.options.replacement, index + 1)
+            }
+        }
+    }
+
+    fn redact(&mut self, data: &[u8], is_text: bool) -> Result<Vec<u8>, ImperatorError> {
+        let replacements = if is_text {
+            self.text_replacements(data)?
+        } else {
+            self.binary_replacements(data)?
+        };
+
+        let mut out = Vec::with_capacity(data.len());
+        let mut last = 0;
+        for (range, replacement) in replacements {
+            out.extend_from_slice(&data[last..range.start]);
+            out.extend_from_slice(&replacement);
+            last = range.end;
+        }
+        out.extend_from_slice(&data[last..]);
+        Ok(out)
+    }
+
+    fn binary_replacements(&mut self, data: &[u8]) -> Result<Replacements, ImperatorError> {
+        let resolver = self.resolver;
+        let mut result = Vec::new();
+        let mut reader = binary::TokenReader::from_slice(data);
+        let mut last_key: Option<&str> = None;
+        let mut field: Option<&str> = None;
+        let mut containers: Vec<Option<&str>> = Vec::new();
+        while let Some(token) = reader.next()? {
+            match token {
+                binary::Token::Id(id) => {
+                    last_key = resolver.resolve(id);
+                    if let Some(key) = last_key {
+                        if let Some(key) = self.required().find(|x| *x == key) {
+                            self.resolved.insert(key);
+                        }
+                    }
+                    continue;
+                }
+                binary::Token::Equal => {
+                    field = last_key.take();
+                    continue;
+                }
+                binary::Token::Open => containers.push(field.take()),
+                binary::Token::Close => {
+                    containers.pop();
+                }
+                binary::Token::Quoted(x) => {
+                    let parent = containers.last().copied().flatten().map(str::as_bytes);
+                    let target = field.and_then(|key| self.target(key.as_bytes(), parent));
+                    if let Some(target) = target {
+                        let original = x.as_bytes().to_vec();
+                        let end = reader.position();
+                        let start = end - original.len() - 4;
+                        let replacement = self.replacement(target, &original);
+                        let mut encoded = Vec::new();
+                        binary::Token::Quoted(Scalar::new(replacement.as_bytes()))
+                            .write(&mut encoded)?;
+                        result.push((start..end, encoded));
+                    }
+                }
+                _ => {}
+            }
+
+            last_key = None;
+            field = None;
+        }
+
+        Ok(result)
+    }
+
+    fn text_replacements(&mut self, data: &[u8]) -> Result<Replacements, ImperatorError> {
+        let mut result = Vec::new();
+        let mut reader = text::TokenReader::from_slice(data);
+        let mut last_key: Option<&[u8]> = None;
+        let mut field: Option<&[u8]> = None;
+        let mut containers: Vec<Option<&[u8]>> = Vec::new();
+        while let Some(token) = reader.next().map_err(jomini::Error::from)? {
+            match token {
+                text::Token::Unquoted(x) => {
+                    let len = x.as_bytes().len();
+                    let end = reader.position();
+                    last_key = Some(&data[end - len..end]);
+                    field = None;
+                }
+                text::Token::Quoted(x) => {
+                    let len = x.as_bytes().len();
+                    let end = reader.position();
+                    let parent = containers.last().copied().flatten();
+                    let target = field.take().and_then(|key| self.target(key, parent));
+                    if let Some(target) = target {
+                        let original = data[end - len - 1..end - 1].to_vec();
+                        let replacement = self.replacement(target, &original);
+                        let mut quoted = Vec::with_capacity(replacement.len() + 2);
+                        quoted.push(b'"');
+                        for &b in replacement.as_bytes() {
+                            if matches!(b, b'"' | b'\\') {
+                                quoted.push(b'\\');
+                            }
+                            quoted.push(b);
+                        }
+                        quoted.push(b'"');
+                        result.push((end - len - 2..end, quoted));
+                        last_key = None;
+                    } else {
+                        last_key = Some(&data[end - len - 1..end - 1]);
+                    }
+                }
+                text::Token::Operator(_) => field = last_key.take(),
+                text::Token::Open => {
+                    containers.push(field.take());
+                    last_key = None;
+                }
+                text::Token::Close => {
+                    containers.pop();
+                    last_key = None;
+                    field = None;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,76 @@
+/// Imperator gamestate fields that hold dates
+const IMPERATOR_DATE_FIELDS: &[&str] =
+    &["birth_date", "date", "death_date", "end_date", "start_date"];
+
+/// Imperator gamestate fields that hold integers which may be mistaken for
+/// dates
+const IMPERATOR_NUMBER_FIELDS: &[&str] = &["play_time", "random_seed", "save_game_version", "seed"];
+
+/// Describes which fields hold dates and which hold plain numbers, as the
+/// binary format encodes both as 32 bit integers.
+///
+/// A field is identified by its key or, for values within an array, the key
+/// of the array.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSchema {
+    dates: Vec<String>,
+    numbers: Vec<String>,
+}
+
+impl Default for FieldSchema {
+    fn default() -> Self {
+        Self::imperator()
+    }
+}
+
+impl FieldSchema {
+    /// Create a schema from the given date and number fields
+    pub fn new(
+        dates: impl IntoIterator<Item = impl Into<String>>,
+        numbers: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        FieldSchema {
+            dates: dates.into_iter().map(Into::into).collect(),
+            numbers: numbers.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// The schema for Imperator gamestate keys
+    pub fn imperator() -> Self {
+        Self::new(
+            IMPERATOR_DATE_FIELDS.iter().copied(),
+            IMPERATOR_NUMBER_FIELDS.iter().copied(),
+        )
+    }
+
+    /// The fields known to hold dates
+    pub fn dates(&self) -> &[String] {
+        &self.dates
+    }
+
+    /// The fields known to hold numbers
+    pub fn numbers(&self) -> &[String] {
+        &self.numbers
+    }
+
+    pub(crate) fn is_date(&self, field: &str) -> bool {
+        self.dates.iter().any(|x| x == field)
+    }
+
+    pub(crate) fn is_number(&self, field: &str) -> bool {
+        self.numbers.iter().any(|x| x == field)
+    }
+}
+
+/// How the melter decides if an integer should be written out as a date
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum DateDetection {
+    /// Integers that decode to a plausible date are written as dates, except
+    /// for the value of the `seed` field
+    #[default]
+    Heuristic,
+
+    /// Consult the schema first and only fall back to the heuristic for
+    /// fields the schema doesn't know about
+    Schema(FieldSchema),
+}
@@ -0,0 +1,192 @@
+use crate::{
+    fields::{self, Field, FieldKey},
+    flavor::ImperatorFlavor,
+    models::{Character, Country, Database, Pop, Province},
+    ImperatorError, ImperatorErrorKind, ImperatorFile,
+};
+use jomini::{
+    binary::{BinaryDeserializer, TokenResolver},
+    envelope::{ReaderAt, SaveContentKind},
+    text,
+};
+use serde::de::DeserializeOwned;
+use std::{io::Read, ops::Range};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Text,
+    Binary,
+}
+
+/// The gamestate of a save indexed by its top level sections, so that
+/// sections can be deserialized independently of each other and the rest of
+/// the save.
+///
+/// Only sections with an object or array value are indexed.
+#[derive(Debug, Clone)]
+pub struct GamestateSections {
+    data: Vec<u8>,
+    encoding: Encoding,
+    sections: Vec<(String, Range<usize>)>,
+}
+
+impl GamestateSections {
+    /// Read the gamestate and scan it for its top level sections. The
+    /// resolver is used to name sections of binary saves. Sections with
+    /// unresolved names are not indexed. Top level tokens that aren't part
+    /// of a `key=value` field are skipped.
+    pub fn from_file<R, Resolver>(
+        file: &ImperatorFile<R>,
+        resolver: Resolver,
+    ) -> Result<Self, ImperatorError>
+    where
+        R: ReaderAt,
+        Resolver: TokenResolver,
+    {
+        let mut data = Vec::new();
+        let encoding = match file.gamestate()? {
+            SaveContentKind::Text(mut x) => {
+                x.read_to_end(&mut data)?;
+                Encoding::Text
+            }
+            SaveContentKind::Binary(mut x) => {
+                x.read_to_end(&mut data)?;
+                Encoding::Binary
+            }
+        };
+
+        let fields: Vec<Field> = match encoding {
+            Encoding::Text => fields::text_fields(&data).collect::<Result<_, _>>()?,
+            Encoding::Binary => fields::binary_fields(&data, false).collect::<Result<_, _>>()?,
+        };
+
+        let sections = fields
+            .into_iter()
+            .filter_map(|field| {
+                let contents = field.contents?;
+                let key = match field.key {
+                    FieldKey::Id(id) => String::from(resolver.resolve(id)?),
+                    FieldKey::Name(name) => name,
+                    FieldKey::Int => return None,
+                };
+                Some((key, contents))
+            })
+            .collect();
+
+        Ok(GamestateSections {
+            data,
+            encoding,
+            sections,
+        })
+    }
+
+    /// The names of the indexed sections in the order they appear
+    pub fn keys(&self) -> impl Iterator<Item = &str> + '_ {
+        self.sections.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// Deserialize the contents of the first section with the given name.
+    /// Returns `None` when the save does not contain the section.
+    pub fn deserialize<T, Resolver>(
+        &self,
+        key: &str,
+        resolver: Resolver,
+    ) -> Result<Option<T>, ImperatorError>
+    where
+        T: DeserializeOwned,
+        Resolver: TokenResolver,
+    {
+        let Some((_, range)) = self.sections.iter().find(|(name, _)| name == key) else {
+            return Ok(None);
+        };
+
+        let data = &self.data[range.clone()];
+        let result = match self.encoding {
+            Encoding::Text => text::de::from_utf8_slice(data),
+            Encoding::Binary => BinaryDeserializer::builder_flavor(ImperatorFlavor::new())
+                .deserialize_slice(data, &resolver),
+        };
+
+        result
+            .map(Some)
+            .map_err(|e| ImperatorErrorKind::Deserialize(e).into())
+    }
+}
+
+/// The largest databases of a save
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameDatabases {
+    pub countries: Database<Country>,
+    pub characters: Database<Character>,
+    pub pops: Database<Pop>,
+    pub provinces: Database<Province>,
+}
+
+impl GameDatabases {
+    /// Deserialize the `country`, `character`, `population` and `provinces`
+    /// sections concurrently, each on its own thread. Errors with
+    /// [`ImperatorErrorKind::MissingSection`] when a section is absent.
+    pub fn from_sections<Resolver>(
+        sections: &GamestateSections,
+        resolver: Resolver,
+    ) -> Result<Self, ImperatorError>
+    where
+        Resolver: TokenResolver + Sync,
+    {
+        fn section<T: DeserializeOwned>(
+            sections: &GamestateSections,
+            key: &str,
+            resolver: &impl TokenResolver,
+        ) -> Result<Database<T>, ImperatorError> {
+            sections.deserialize(key, resolver)?.ok_or_else(|| {
+                ImperatorErrorKind::MissingSection {
+                    name: String::from(key),
+                }
+                .into()
+            })
+        }
+
+        fn join<T>(handle: std::thread::ScopedJoinHandle<'_, T>) -> T {
+            handle.join().expect("deserialization thread panicked")
+        }
+
+        let resolver = &resolver;
+        std::thread::scope(|scope| {
+            let countries = scope.spawn(|| section(sections, "country", resolver));
+            let characters = scope.spawn(|| section(sections, "character", resolver));
+            let pops = scope.spawn(|| section(sections, "population", resolver));
+            let provinces = section(sections, "provinces", resolver);
+
+            Ok(GameDatabases {
+                countries: join(countries)?,
+                characters: join(characters)?,
+                pops: join(pops)?,
+                provinces: provinces?,
+            })
+        })
+    }
+}
+
+pub trait ImperatorParallelDeserialize {
+    /// Deserialize the save's largest databases concurrently. See
+    /// [`GameDatabases::from_sections`].
+    fn deserialize_databases<Resolver>(
+        &mut self,
+        resolver: Resolver,
+    ) -> Result<GameDatabases, ImperatorError>
+    where
+        Resolver: TokenResolver + Sync;
+}
+
+impl<R: ReaderAt> ImperatorParallelDeserialize for &'_ ImperatorFile<R> {
+    fn deserialize_databases<Resolver>(
+        &mut self,
+        resolver: Resolver,
+    ) -> Result<GameDatabases, ImperatorError>
+    where
+        Resolver: TokenResolver + Sync,
+    {
+        let sections = GamestateSections::from_file(self, &resolver)?;
+        GameDatabases::from_sections(&sections, resolver)
+    }
+}
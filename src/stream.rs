@@ -0,0 +1,355 @@
+use crate::{
+    models::Metadata, ImperatorError, ImperatorErrorKind, ImperatorFile, ImperatorMetadata,
+};
+use flate2::{Decompress, FlushDecompress, Status};
+use futures_util::io::{AsyncRead, AsyncReadExt};
+use jomini::{
+    binary::TokenResolver,
+    envelope::{SaveHeader, SaveHeaderKind},
+};
+use std::io::{self, Cursor, Read};
+
+const LOCAL_FILE_SIGNATURE: &[u8; 4] = b"PK\x03\x04";
+const CENTRAL_DIRECTORY_SIGNATURE: &[u8; 4] = b"PK\x01\x02";
+const DATA_DESCRIPTOR_SIGNATURE: &[u8; 4] = b"PK\x07\x08";
+const LOCAL_FILE_HEADER_LEN: usize = 30;
+const MAX_HEADER_LEN: usize = 33;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Reads a save from an [`AsyncRead`] without first buffering the file.
+///
+/// Construction reads the header and, when it is available up front, the
+/// metadata. [`into_file`](ImperatorAsyncReader::into_file) then reads the
+/// rest of the input, decompressing the gamestate as it arrives, and returns
+/// an uncompressed [`ImperatorFile`] that the melt and deserialize APIs
+/// accept.
+///
+/// Zip entries are read in the order they are stored rather than through the
+/// central directory at the end of the file. When the gamestate precedes the
+/// metadata entry of a split save, it is retained compressed until
+/// [`into_file`](ImperatorAsyncReader::into_file).
+pub struct ImperatorAsyncReader<R> {
+    reader: StreamReader<R>,
+    header: SaveHeader,
+    raw_header: Vec<u8>,
+    meta: Option<Vec<u8>>,
+
+    /// The inflated gamestate, preceded by room for the header
+    gamestate: Option<Vec<u8>>,
+
+    /// The compressed gamestate, when it was read before the metadata
+    deflated: Option<Vec<u8>>,
+    zipped: bool,
+}
+
+impl<R: AsyncRead + Unpin> ImperatorAsyncReader<R> {
+    /// Read the header and metadata of a save
+    pub async fn new(reader: R) -> Result<Self, ImperatorError> {
+        let mut reader = StreamReader::new(reader);
+        reader.fill(MAX_HEADER_LEN).await?;
+        let header = SaveHeader::from_slice(reader.buffered())?;
+        if matches!(header.kind(), SaveHeaderKind::Other(_)) {
+            return Err(ImperatorErrorKind::InvalidHeader.into());
+        }
+
+        let raw_header = reader.take(header.header_len()).await?;
+        let meta_len = usize::try_from(header.metadata_len())
+            .map_err(|_| ImperatorErrorKind::InvalidHeader)?;
+        let meta = reader.take(meta_len).await?;
+        let zipped = reader.peek_signature(LOCAL_FILE_SIGNATURE).await?;
+
+        let mut result = ImperatorAsyncReader {
+            reader,
+            header,
+            raw_header,
+            meta: (meta_len > 0).then_some(meta),
+            gamestate: None,
+            deflated: None,
+            zipped,
+        };
+
+        // Split saves store the metadata in the zip, which is typically
+        // written before the gamestate
+        if result.zipped && result.meta.is_none() {
+            while result.meta.is_none() && result.read_entry(true).await? {}
+        }
+
+        Ok(result)
+    }
+
+    pub fn header(&self) -> &SaveHeader {
+        &self.header
+    }
+
+    /// Deserialize the metadata. Returns `None` when the save does not have
+    /// a separate metadata section, as is the case for debug saves, where the
+    /// metadata is only available once the whole save has been read.
+    pub fn metadata<Resolver>(&self, resolver: Resolver) -> Result<Option<Metadata>, ImperatorError>
+    where
+        Resolver: TokenResolver,
+    {
+        let Some(meta) = self.meta.as_deref() else {
+            return Ok(None);
+        };
+
+        let mut data = vec![0u8; self.header.header_len()];
+        data.extend_from_slice(meta);
+        self.write_header(&mut data, meta.len());
+        let file = ImperatorFile::from_slice(data)?;
+        file.metadata(resolver).map(Some)
+    }
+
+    /// Read the remainder of the save, decompressing the gamestate, into an
+    /// uncompressed save
+    pub async fn into_file(mut self) -> Result<ImperatorFile<Cursor<Vec<u8>>>, ImperatorError> {
+        if !self.zipped {
+            let mut data = self.raw_header;
+            data.extend_from_slice(self.meta.as_deref().unwrap_or_default());
+            self.reader.read_to_end(&mut data).await?;
+            return Ok(ImperatorFile::from_slice(data)?);
+        }
+
+        if let Some(deflated) = self.deflated.take() {
+            let mut data = vec![0u8; self.header.header_len()];
+            flate2::read::DeflateDecoder::new(deflated.as_slice()).read_to_end(&mut data)?;
+            self.gamestate = Some(data);
+        }
+
+        while self.gamestate.is_none() && self.read_entry(false).await? {}
+        let mut gamestate =
+            self.gamestate
+                .take()
+                .ok_or_else(|| ImperatorErrorKind::MissingZipEntry {
+                    name: String::from("gamestate"),
+                })?;
+
+        // The gamestate repeats the metadata as a prefix, which is what an
+        // uncompressed save's metadata length is expected to describe
+        let body = &gamestate[self.header.header_len()..];
+        let meta_len = match self.meta.as_deref() {
+            Some(meta) if body.starts_with(meta) => meta.len(),
+            _ => 0,
+        };
+
+        self.write_header(&mut gamestate, meta_len);
+        Ok(ImperatorFile::from_slice(gamestate)?)
+    }
+
+    /// Write the header of an uncompressed save of the same encoding into
+    /// the space reserved at the start of the data
+    fn write_header(&self, data: &mut [u8], meta_len: usize) {
+        let mut header = self.header.clone();
+        let kind = if header.kind().is_text() {
+            SaveHeaderKind::Text
+        } else {
+            SaveHeaderKind::Binary
+        };
+        header.set_kind(kind);
+        header.set_metadata_len(meta_len as u64);
+        let _ = header.write(&mut data[..header.header_len()]);
+    }
+
+    /// Read the next zip entry, retaining it if it is the metadata or
+    /// gamestate. When `defer` is set, a deflated gamestate is retained
+    /// without being inflated. Returns false once all entries have been
+    /// read.
+    async fn read_entry(&mut self, defer: bool) -> Result<bool, ImperatorError> {
+        if !self.reader.peek_signature(LOCAL_FILE_SIGNATURE).await? {
+            return if self
+                .reader
+                .peek_signature(CENTRAL_DIRECTORY_SIGNATURE)
+                .await?
+            {
+                Ok(false)
+            } else {
+                Err(zip_stream("expected a local file header"))
+            };
+        }
+
+        let fixed = self.reader.take(LOCAL_FILE_HEADER_LEN).await?;
+        let u16_at = |offset: usize| u16::from_le_bytes([fixed[offset], fixed[offset + 1]]);
+        let flags = u16_at(6);
+        let method = u16_at(8);
+        let compressed_size = u32::from_le_bytes(fixed[18..22].try_into().unwrap());
+        let name = self.reader.take(usize::from(u16_at(26))).await?;
+        let extra = self.reader.take(usize::from(u16_at(28))).await?;
+
+        let is_meta = name == b"meta";
+        let is_gamestate = name == b"gamestate";
+        let defer = defer && is_gamestate && method == 8;
+        let mut data = if is_gamestate && !defer {
+            vec![0u8; self.header.header_len()]
+        } else {
+            Vec::new()
+        };
+
+        let has_descriptor = flags & 0x08 != 0;
+        match method {
+            0 if has_descriptor => {
+                return Err(zip_stream("stored entries must declare their size"));
+            }
+            0 => {
+                let stored = self.reader.take(compressed_size as usize).await?;
+                data.extend_from_slice(&stored);
+            }
+            8 if defer => self.reader.inflate(None, Some(&mut data)).await?,
+            8 if is_meta || is_gamestate => self.reader.inflate(Some(&mut data), None).await?,
+            8 => self.reader.inflate(None, None).await?,
+            _ => return Err(zip_stream("unsupported compression method")),
+        };
+
+        if has_descriptor {
+            let sizes = if is_zip64(&extra) { 16 } else { 8 };
+            self.reader
+                .skip_signature(DATA_DESCRIPTOR_SIGNATURE)
+                .await?;
+            self.reader.take(4 + sizes).await?;
+        }
+
+        if is_meta {
+            self.meta = Some(data);
+        } else if defer {
+            self.deflated = Some(data);
+        } else if is_gamestate {
+            self.gamestate = Some(data);
+        }
+
+        Ok(true)
+    }
+}
+
+/// Whether the extra field contains a zip64 record
+fn is_zip64(mut extra: &[u8]) -> bool {
+    while let Some((&[lo, hi, len_lo, len_hi], rest)) = extra.split_first_chunk::<4>() {
+        if u16::from_le_bytes([lo, hi]) == 0x0001 {
+            return true;
+        }
+        let len = usize::from(u16::from_le_bytes([len_lo, len_hi]));
+        extra = rest.get(len..).unwrap_or_default();
+    }
+    false
+}
+
+fn zip_stream(msg: &str) -> ImperatorError {
+    ImperatorErrorKind::ZipStream(String::from(msg)).into()
+}
+
+/// A buffered reader that exposes the buffer for parsing in place
+struct StreamReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: AsyncRead + Unpin> StreamReader<R> {
+    fn new(inner: R) -> Self {
+        StreamReader {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn buffered(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+
+    /// Read another chunk, returning the number of bytes read
+    async fn read_more(&mut self) -> io::Result<usize> {
+        if self.pos > 0 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        let len = self.buf.len();
+        self.buf.resize(len + CHUNK_SIZE, 0);
+        let read = self.inner.read(&mut self.buf[len..]).await;
+        self.buf.truncate(len + *read.as_ref().unwrap_or(&0));
+        read
+    }
+
+    /// Buffer up to `amt` bytes, stopping early only at the end of input
+    async fn fill(&mut self, amt: usize) -> io::Result<()> {
+        while self.buffered().len() < amt {
+            if self.read_more().await? == 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn take(&mut self, amt: usize) -> io::Result<Vec<u8>> {
+        self.fill(amt).await?;
+        let data = self
+            .buffered()
+            .get(..amt)
+            .ok_or(io::ErrorKind::UnexpectedEof)?
+            .to_vec();
+        self.consume(amt);
+        Ok(data)
+    }
+
+    async fn peek_signature(&mut self, signature: &[u8; 4]) -> io::Result<bool> {
+        self.fill(signature.len()).await?;
+        Ok(self.buffered().starts_with(signature))
+    }
+
+    async fn skip_signature(&mut self, signature: &[u8; 4]) -> io::Result<()> {
+        if self.peek_signature(signature).await? {
+            self.consume(signature.len());
+        }
+        Ok(())
+    }
+
+    async fn read_to_end(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        out.extend_from_slice(self.buffered());
+        self.consume(self.buffered().len());
+        self.inner.read_to_end(out).await?;
+        Ok(())
+    }
+
+    /// Decompress a raw deflate stream, consuming exactly its bytes. The
+    /// decompressed data is appended to `out`, or discarded when there is no
+    /// `out`, and the compressed bytes are appended to `raw`.
+    async fn inflate(
+        &mut self,
+        mut out: Option<&mut Vec<u8>>,
+        mut raw: Option<&mut Vec<u8>>,
+    ) -> io::Result<()> {
+        let mut decompress = Decompress::new(false);
+        let mut scratch = Vec::new();
+        loop {
+            let out = match out.as_deref_mut() {
+                Some(out) => out,
+                None => {
+                    scratch.clear();
+                    &mut scratch
+                }
+            };
+
+            if out.capacity() - out.len() < CHUNK_SIZE {
+                out.reserve(CHUNK_SIZE.max(out.len()));
+            }
+
+            let (total_in, total_out) = (decompress.total_in(), decompress.total_out());
+            let status = decompress.decompress_vec(self.buffered(), out, FlushDecompress::None)?;
+            let consumed = (decompress.total_in() - total_in) as usize;
+            if let Some(raw) = raw.as_deref_mut() {
+                raw.extend_from_slice(&self.buffered()[..consumed]);
+            }
+            self.consume(consumed);
+            if status == Status::StreamEnd {
+                return Ok(());
+            }
+
+            let stalled = decompress.total_in() == total_in && decompress.total_out() == total_out;
+            if stalled && self.read_more().await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{
+    models::{Country, Database, PlayedCountry},
+    GameVersion, GamestateSections, ImperatorDate, ImperatorError, ImperatorFile,
+    ImperatorMetadata,
+};
+use jomini::{
+    binary::TokenResolver,
+    envelope::{JominiFileKind, ReaderAt, SaveHeaderKind},
+};
+#[cfg(feature = "serialize")]
+use {
+    jomini::common::PdsDate,
+    serde::{Serialize, Serializer},
+};
+
+/// A file stored within a compressed save
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct SummaryEntry {
+    name: String,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl SummaryEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+}
+
+/// An overview of a save that is assembled from the header and metadata. The
+/// gamestate is not read.
+///
+/// Imperator's metadata records the name of the played country but not its
+/// tag or the name of the player, see [`PlayerSummary`] for those.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct SaveSummary {
+    #[cfg_attr(feature = "serialize", serde(serialize_with = "serialize_display"))]
+    version: GameVersion,
+    #[cfg_attr(feature = "serialize", serde(serialize_with = "serialize_date"))]
+    date: ImperatorDate,
+    country_name: Option<String>,
+    ironman: bool,
+    dlcs: Vec<String>,
+    play_time: i32,
+    #[cfg_attr(feature = "serialize", serde(serialize_with = "serialize_debug"))]
+    kind: SaveHeaderKind,
+    file_size: u64,
+    uncompressed_size: u64,
+    entries: Vec<SummaryEntry>,
+}
+
+impl SaveSummary {
+    /// Summarize a save held in memory
+    pub fn from_slice<Resolver>(data: &[u8], resolver: Resolver) -> Result<Self, ImperatorError>
+    where
+        Resolver: TokenResolver,
+    {
+        let file = ImperatorFile::from_slice(data)?;
+        let entries = if matches!(file.kind(), JominiFileKind::Zip(_)) {
+            let archive = rawzip::ZipArchive::from_slice(data)?;
+            let mut result = Vec::new();
+            let mut entries = archive.entries();
+            while let Some(entry) = entries.next_entry()? {
+                result.push(summary_entry(&entry));
+            }
+            result
+        } else {
+            Vec::new()
+        };
+
+        Self::summarize(&file, data.len() as u64, entries, resolver)
+    }
+
+    /// Summarize a save on disk
+    pub fn from_file<Resolver>(
+        file: std::fs::File,
+        resolver: Resolver,
+    ) -> Result<Self, ImperatorError>
+    where
+        Resolver: TokenResolver,
+    {
+        let file_size = file.metadata()?.len();
+        let save = ImperatorFile::from_file(file.try_clone()?)?;
+        let entries = if matches!(save.kind(), JominiFileKind::Zip(_)) {
+            let mut buf = vec![0u8; rawzip::RECOMMENDED_BUFFER_SIZE];
+            let archive = rawzip::ZipArchive::from_file(file, &mut buf)?;
+            let mut result = Vec::new();
+            let mut entries = archive.entries(&mut buf);
+            while let Some(entry) = entries.next_entry()? {
+                result.push(summary_entry(&entry));
+            }
+            result
+        } else {
+            Vec::new()
+        };
+
+        Self::summarize(&save, file_size, entries, resolver)
+    }
+
+    fn summarize<R, Resolver>(
+        file: &ImperatorFile<R>,
+        file_size: u64,
+        entries: Vec<SummaryEntry>,
+        resolver: Resolver,
+    ) -> Result<Self, ImperatorError>
+    where
+        R: ReaderAt,
+        Resolver: TokenResolver,
+    {
+        let header = file.header();
+        let meta = file.metadata(&resolver)?;
+
+        let uncompressed_size = if entries.is_empty() {
+            file_size
+        } else {
+            let inlined = if entries.iter().any(|x| x.name == "meta") {
+                0
+            } else {
+                header.metadata_len()
+            };
+            let contents: u64 = entries.iter().map(|x| x.uncompressed_size).sum();
+            header.header_len() as u64 + inlined + contents
+        };
+
+        Ok(SaveSummary {
+            version: meta.version,
+            date: meta.date,
+            country_name: meta.meta_player_name,
+            ironman: meta.ironman,
+            dlcs: meta.enabled_dlcs,
+            play_time: meta.play_time,
+            kind: header.kind(),
+            file_size,
+            uncompressed_size,
+            entries,
+        })
+    }
+
+    pub fn version(&self) -> &GameVersion {
+        &self.version
+    }
+
+    pub fn date(&self) -> ImperatorDate {
+        self.date
+    }
+
+    /// The name of the country played, as shown in the save browser
+    pub fn country_name(&self) -> Option<&str> {
+        self.country_name.as_deref()
+    }
+
+    pub fn ironman(&self) -> bool {
+        self.ironman
+    }
+
+    pub fn dlcs(&self) -> &[String] {
+        &self.dlcs
+    }
+
+    /// Play time in seconds
+    pub fn play_time(&self) -> i32 {
+        self.play_time
+    }
+
+    pub fn kind(&self) -> SaveHeaderKind {
+        self.kind
+    }
+
+    /// The size of the save on disk
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// The size of the save if it were written uncompressed
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// The files within the save's zip. Empty for uncompressed saves.
+    pub fn entries(&self) -> &[SummaryEntry] {
+        &self.entries
+    }
+}
+
+fn summary_entry(entry: &rawzip::ZipFileHeaderRecord) -> SummaryEntry {
+    SummaryEntry {
+        name: String::from_utf8_lossy(entry.file_path().as_ref()).into_owned(),
+        compressed_size: entry.compressed_size_hint(),
+        uncompressed_size: entry.uncompressed_size_hint(),
+    }
+}
+
+/// The first player of a save and the tag of their country.
+///
+/// These are read from the `played_country` and `country` sections of the
+/// gamestate, so unlike [`SaveSummary`], the gamestate is decompressed and
+/// indexed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PlayerSummary {
+    name: Option<String>,
+    tag: Option<String>,
+}
+
+impl PlayerSummary {
+    pub fn from_file<R, Resolver>(
+        file: &ImperatorFile<R>,
+        resolver: Resolver,
+    ) -> Result<Self, ImperatorError>
+    where
+        R: ReaderAt,
+        Resolver: TokenResolver,
+    {
+        let sections = GamestateSections::from_file(file, &resolver)?;
+        let Some(played) = sections.deserialize::<PlayedCountry, _>("played_country", &resolver)?
+        else {
+            return Ok(PlayerSummary::default());
+        };
+
+        let tag = match played.country {
+            Some(id) => sections
+                .deserialize::<Database<Country>, _>("country", &resolver)?
+                .and_then(|mut x| x.database.remove(&id))
+                .map(|x| x.tag),
+            None => None,
+        };
+
+        Ok(PlayerSummary {
+            name: played.player_name,
+            tag,
+        })
+    }
+
+    /// The name of the player
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The tag of the player's country
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+}
+
+#[cfg(feature = "serialize")]
+fn serialize_display<T: std::fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+#[cfg(feature = "serialize")]
+fn serialize_date<S: Serializer>(date: &ImperatorDate, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&date.game_fmt())
+}
+
+#[cfg(feature = "serialize")]
+fn serialize_debug<T: std::fmt::Debug, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{:?}", value))
+}
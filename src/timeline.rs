@@ -0,0 +1,147 @@
+use crate::{
+    models::{Country, CurrencyData, Database},
+    DeserializeImperator, ImperatorDate, ImperatorError, ImperatorErrorKind, ImperatorFile,
+};
+use jomini::binary::TokenResolver;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Deserialize)]
+struct Snapshot {
+    date: ImperatorDate,
+    #[serde(default)]
+    random_seed: Option<i64>,
+    #[serde(default)]
+    playthrough_id: Option<String>,
+    #[serde(default)]
+    country: Database<Country>,
+}
+
+/// A save within a campaign timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEntry {
+    path: PathBuf,
+    date: ImperatorDate,
+    countries: HashMap<String, CurrencyData>,
+}
+
+impl TimelineEntry {
+    /// The file the entry was read from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The in-game date of the save
+    pub fn date(&self) -> ImperatorDate {
+        self.date
+    }
+
+    /// Metrics of every country alive at this point, keyed by tag
+    pub fn countries(&self) -> &HashMap<String, CurrencyData> {
+        &self.countries
+    }
+}
+
+/// Saves from a single playthrough ordered by their in-game date
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CampaignTimeline {
+    random_seed: Option<i64>,
+    playthrough_id: Option<String>,
+    entries: Vec<TimelineEntry>,
+}
+
+impl CampaignTimeline {
+    /// Build a timeline from every `.rome` save in a directory
+    pub fn from_dir<P, Resolver>(dir: P, resolver: Resolver) -> Result<Self, ImperatorError>
+    where
+        P: AsRef<Path>,
+        Resolver: TokenResolver,
+    {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|x| x == "rome") {
+                paths.push(path);
+            }
+        }
+
+        Self::from_paths(paths, resolver)
+    }
+
+    /// Build a timeline from the given saves. Returns an error if any of the
+    /// saves belong to a different playthrough, as determined by the random
+    /// seed and playthrough id. A save missing either value that another save
+    /// has, or missing both, is not considered part of the same playthrough.
+    pub fn from_paths<I, P, Resolver>(paths: I, resolver: Resolver) -> Result<Self, ImperatorError>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+        Resolver: TokenResolver,
+    {
+        let mut timeline = CampaignTimeline::default();
+        for path in paths {
+            let path = path.into();
+            let file = ImperatorFile::from_file(std::fs::File::open(&path)?)?;
+            let snapshot: Snapshot = (&file).deserialize(&resolver)?;
+
+            if timeline.entries.is_empty() {
+                timeline.random_seed = snapshot.random_seed;
+                timeline.playthrough_id = snapshot.playthrough_id;
+            } else {
+                let identified =
+                    snapshot.random_seed.is_some() || snapshot.playthrough_id.is_some();
+                if !identified
+                    || timeline.random_seed != snapshot.random_seed
+                    || timeline.playthrough_id != snapshot.playthrough_id
+                {
+                    return Err(ImperatorErrorKind::PlaythroughMismatch { path }.into());
+                }
+            }
+
+            let countries = snapshot
+                .country
+                .database
+                .into_values()
+                .map(|x| (x.tag, x.currency_data))
+                .collect();
+
+            timeline.entries.push(TimelineEntry {
+                path,
+                date: snapshot.date,
+                countries,
+            });
+        }
+
+        timeline
+            .entries
+            .sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.path.cmp(&b.path)));
+        Ok(timeline)
+    }
+
+    /// The saves ordered by date
+    pub fn entries(&self) -> &[TimelineEntry] {
+        &self.entries
+    }
+
+    pub fn random_seed(&self) -> Option<i64> {
+        self.random_seed
+    }
+
+    pub fn playthrough_id(&self) -> Option<&str> {
+        self.playthrough_id.as_deref()
+    }
+
+    /// The metrics of a country over time, skipping saves where the country
+    /// does not exist
+    pub fn country<'a>(
+        &'a self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = (ImperatorDate, &'a CurrencyData)> + 'a {
+        self.entries
+            .iter()
+            .filter_map(move |entry| entry.countries.get(tag).map(|x| (entry.date, x)))
+    }
+}
@@ -0,0 +1,252 @@
+mod parse;
+
+use crate::{ImperatorError, ImperatorErrorKind};
+use jomini::binary::TokenResolver;
+use parse::BINARY_MAGIC;
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+/// Environment variable consulted for the path to the token file
+pub const TOKENS_ENV: &str = "IMPERATOR_TOKENS";
+
+/// The on-disk formats of a token file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenFormat {
+    /// Space delimited lines of a hexadecimal id and the token:
+    ///
+    /// ```plain
+    /// 0x00ee version
+    /// 0x045a count
+    /// ```
+    Text,
+
+    /// The magic `ITOK`, a little endian `u32` count, and then for each
+    /// token a little endian `u16` id followed by a length prefixed string
+    /// with a `u8` length.
+    Binary,
+}
+
+/// A lookup of binary token ids to their textual representation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenTable {
+    lookup: HashMap<u16, String>,
+}
+
+impl TokenTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse tokens in the text format
+    pub fn from_text_lines<R: BufRead>(mut reader: R) -> Result<Self, ImperatorError> {
+        let mut lookup = HashMap::new();
+        let mut line = String::new();
+        let mut line_num = 0;
+        while reader.read_line(&mut line)? != 0 {
+            line_num += 1;
+            let parsed = parse::parse_text_line(&line).map_err(|msg| {
+                ImperatorErrorKind::InvalidTokens(format!("line {}: {}", line_num, msg))
+            })?;
+            if let Some((id, token)) = parsed {
+                lookup.insert(id, String::from(token));
+            }
+            line.clear();
+        }
+
+        Ok(TokenTable { lookup })
+    }
+
+    /// Parse tokens in the binary format
+    pub fn from_binary(data: &[u8]) -> Result<Self, ImperatorError> {
+        let tokens = parse::parse_binary(data).map_err(|(offset, msg)| {
+            ImperatorErrorKind::InvalidTokens(format!("offset {}: {}", offset, msg))
+        })?;
+        let lookup = tokens
+            .into_iter()
+            .map(|(id, token)| (id, String::from(token)))
+            .collect();
+        Ok(TokenTable { lookup })
+    }
+
+    /// Parse tokens, detecting the format from the data
+    pub fn from_slice(data: &[u8]) -> Result<Self, ImperatorError> {
+        match TokenFormat::detect(data) {
+            TokenFormat::Binary => Self::from_binary(data),
+            TokenFormat::Text => Self::from_text_lines(data),
+        }
+    }
+
+    /// Read tokens from a file in either format
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ImperatorError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|source| ImperatorErrorKind::TokenFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_slice(&data)
+    }
+
+    /// Read tokens from the path in the `IMPERATOR_TOKENS` environment
+    /// variable. When it is not set, the first of these that exists is
+    /// used:
+    ///
+    /// - `assets/imperator.txt` or `assets/imperator.bin`
+    /// - `imperator-save/tokens.txt` or `imperator-save/tokens.bin` within
+    ///   the user's config directory (`$XDG_CONFIG_HOME`, `~/.config` or
+    ///   `%APPDATA%`)
+    pub fn from_env() -> Result<Self, ImperatorError> {
+        if let Some(path) = std::env::var_os(TOKENS_ENV) {
+            return Self::from_path(path);
+        }
+
+        let searched = Self::search_paths();
+        match searched.iter().find(|x| x.is_file()) {
+            Some(path) => Self::from_path(path),
+            None => Err(ImperatorErrorKind::TokensNotFound { searched }.into()),
+        }
+    }
+
+    fn search_paths() -> Vec<PathBuf> {
+        let mut paths = vec![
+            PathBuf::from("assets/imperator.txt"),
+            PathBuf::from("assets/imperator.bin"),
+        ];
+
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|x| Path::new(&x).join(".config")))
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from));
+        if let Some(config) = config {
+            let dir = config.join("imperator-save");
+            paths.push(dir.join("tokens.txt"));
+            paths.push(dir.join("tokens.bin"));
+        }
+
+        paths
+    }
+
+    /// Add a token, returning the previous token for the id if there was one
+    pub fn insert(&mut self, id: u16, token: impl Into<String>) -> Option<String> {
+        self.lookup.insert(id, token.into())
+    }
+
+    pub fn get(&self, id: u16) -> Option<&str> {
+        self.lookup.get(&id).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lookup.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lookup.is_empty()
+    }
+
+    /// The tokens ordered by id
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> + '_ {
+        let mut tokens = self
+            .lookup
+            .iter()
+            .map(|(id, token)| (*id, token.as_str()))
+            .collect::<Vec<_>>();
+        tokens.sort_unstable_by_key(|(id, _)| *id);
+        tokens.into_iter()
+    }
+
+    /// Write the tokens in the given format, ordered by id
+    pub fn write<W: Write>(
+        &self,
+        mut writer: W,
+        format: TokenFormat,
+    ) -> Result<(), ImperatorError> {
+        match format {
+            TokenFormat::Text => {
+                for (id, token) in self.iter() {
+                    writeln!(writer, "{:#06x} {}", id, token)?;
+                }
+            }
+            TokenFormat::Binary => {
+                writer.write_all(BINARY_MAGIC)?;
+                writer.write_all(&(self.lookup.len() as u32).to_le_bytes())?;
+                for (id, token) in self.iter() {
+                    let len = u8::try_from(token.len()).map_err(|_| {
+                        ImperatorErrorKind::InvalidTokens(format!(
+                            "token {:#06x} exceeds 255 bytes",
+                            id
+                        ))
+                    })?;
+                    writer.write_all(&id.to_le_bytes())?;
+                    writer.write_all(&[len])?;
+                    writer.write_all(token.as_bytes())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TokenFormat {
+    /// Binary token files are identified by their magic
+    pub fn detect(data: &[u8]) -> TokenFormat {
+        if data.starts_with(BINARY_MAGIC) {
+            TokenFormat::Binary
+        } else {
+            TokenFormat::Text
+        }
+    }
+}
+
+impl TokenResolver for TokenTable {
+    fn resolve(&self, token: u16) -> Option<&str> {
+        self.get(token)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lookup.is_empty()
+    }
+}
+
+impl FromIterator<(u16, String)> for TokenTable {
+    fn from_iter<T: IntoIterator<Item = (u16, String)>>(iter: T) -> Self {
+        TokenTable {
+            lookup: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_formats_roundtrip() {
+        let table = TokenTable::from_text_lines(&b"0x00ee version\n\n0x045a count\n"[..]).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get(0x00ee), Some("version"));
+
+        let mut text = Vec::new();
+        table.write(&mut text, TokenFormat::Text).unwrap();
+        assert_eq!(text, b"0x00ee version\n0x045a count\n");
+
+        let mut binary = Vec::new();
+        table.write(&mut binary, TokenFormat::Binary).unwrap();
+        assert_eq!(TokenFormat::detect(&binary), TokenFormat::Binary);
+        assert_eq!(TokenTable::from_slice(&binary).unwrap(), table);
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        let err = TokenTable::from_text_lines(&b"0x00ee version\nzz count\n"[..]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid token file: line 2: expected a hexadecimal token id"
+        );
+
+        let err = TokenTable::from_binary(b"ITOK\x01\x00\x00\x00\xee\x00\x07ver").unwrap_err();
+        assert!(matches!(err.kind(), ImperatorErrorKind::InvalidTokens(_)));
+    }
+}
@@ -0,0 +1,48 @@
+// Token file parsing shared by the crate and the build script, which
+// includes this file, so it must only depend on core and std.
+
+/// The magic that begins a binary token file
+pub(crate) const BINARY_MAGIC: &[u8; 4] = b"ITOK";
+
+/// Parse a line of the text format, returning `None` for blank lines
+pub(crate) fn parse_text_line(line: &str) -> Result<Option<(u16, &str)>, &'static str> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+
+    let (id, token) = trimmed
+        .split_once(' ')
+        .ok_or("expected an id and token separated by a space")?;
+    let id = u16::from_str_radix(id.trim_start_matches("0x"), 16)
+        .map_err(|_| "expected a hexadecimal token id")?;
+    Ok(Some((id, token.trim())))
+}
+
+/// Parse the binary format. Errors contain the offset of the entry that
+/// could not be read.
+pub(crate) fn parse_binary(data: &[u8]) -> Result<Vec<(u16, &str)>, (usize, &'static str)> {
+    let data = data
+        .strip_prefix(BINARY_MAGIC)
+        .ok_or((0, "missing token magic"))?;
+    let (count, mut rest) = data
+        .split_first_chunk::<4>()
+        .ok_or((BINARY_MAGIC.len(), "missing token count"))?;
+    let count = u32::from_le_bytes(*count);
+
+    let mut tokens = Vec::with_capacity(count.min(u32::from(u16::MAX) + 1) as usize);
+    for _ in 0..count {
+        let offset = data.len() - rest.len() + BINARY_MAGIC.len();
+        let (&[lo, hi, len], tail) = rest
+            .split_first_chunk::<3>()
+            .ok_or((offset, "truncated token entry"))?;
+        let (token, tail) = tail
+            .split_at_checked(usize::from(len))
+            .ok_or((offset, "truncated token entry"))?;
+        let token = std::str::from_utf8(token).map_err(|_| (offset, "token is not valid utf-8"))?;
+        tokens.push((u16::from_le_bytes([lo, hi]), token));
+        rest = tail;
+    }
+
+    Ok(tokens)
+}
@@ -0,0 +1,173 @@
+use crate::{
+    GameVersion, ImperatorBinaryDeserialization, ImperatorDate, ImperatorError, ImperatorErrorKind,
+    ImperatorFile,
+};
+use jomini::{
+    binary::TokenResolver,
+    common::PdsDate,
+    envelope::{
+        JominiFileKind, ReaderAt, SaveContentKind, SaveDataKind, SaveHeaderKind, SaveMetadataKind,
+    },
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct MetaStamp {
+    version: GameVersion,
+    date: ImperatorDate,
+}
+
+#[derive(Debug, Deserialize)]
+struct GamestateStamp {
+    version: GameVersion,
+    date: ImperatorDate,
+    #[serde(default)]
+    checksum: Option<String>,
+}
+
+/// Information gathered from a save that passed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatedSave {
+    version: GameVersion,
+    date: ImperatorDate,
+    checksum: Option<String>,
+}
+
+impl ValidatedSave {
+    /// The game version the save was written by
+    pub fn version(&self) -> &GameVersion {
+        &self.version
+    }
+
+    /// The in-game date of the save
+    pub fn date(&self) -> ImperatorDate {
+        self.date
+    }
+
+    /// The checksum the game wrote into the gamestate, if present
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
+    }
+}
+
+pub trait ImperatorValidate {
+    /// Check the integrity of a save. The header must agree with the layout
+    /// of the file, compressed saves must contain the expected entries, and
+    /// the metadata must agree with the gamestate on the version and date.
+    ///
+    /// The first failed check is returned as an error.
+    fn validate<Resolver>(&mut self, resolver: Resolver) -> Result<ValidatedSave, ImperatorError>
+    where
+        Resolver: TokenResolver;
+}
+
+impl<R: ReaderAt> ImperatorValidate for &'_ ImperatorFile<R> {
+    fn validate<Resolver>(&mut self, resolver: Resolver) -> Result<ValidatedSave, ImperatorError>
+    where
+        Resolver: TokenResolver,
+    {
+        let header = self.header();
+        let kind = header.kind();
+        let declared = header.metadata_len();
+        let (compressed, metadata_in_zip) = match kind {
+            SaveHeaderKind::Text | SaveHeaderKind::Binary => (false, false),
+            SaveHeaderKind::UnifiedText | SaveHeaderKind::UnifiedBinary => (true, false),
+            SaveHeaderKind::SplitText | SaveHeaderKind::SplitBinary => (true, true),
+            SaveHeaderKind::Other(_) => return Err(ImperatorErrorKind::InvalidHeader.into()),
+        };
+
+        match self.kind() {
+            JominiFileKind::Uncompressed(data) => {
+                if compressed {
+                    return Err(ImperatorErrorKind::HeaderKindMismatch { kind }.into());
+                }
+
+                let body_len = match data {
+                    SaveDataKind::Text(x) => {
+                        std::io::copy(&mut x.body().cursor(), &mut std::io::sink())?
+                    }
+                    SaveDataKind::Binary(x) => {
+                        std::io::copy(&mut x.body().cursor(), &mut std::io::sink())?
+                    }
+                };
+
+                if declared > body_len {
+                    return Err(ImperatorErrorKind::MetadataLength {
+                        declared,
+                        actual: body_len,
+                    }
+                    .into());
+                }
+            }
+            JominiFileKind::Zip(zip) => {
+                if !compressed {
+                    return Err(ImperatorErrorKind::HeaderKindMismatch { kind }.into());
+                }
+
+                if metadata_in_zip {
+                    if zip.read_entry("meta").is_err() {
+                        return Err(ImperatorErrorKind::MissingZipEntry {
+                            name: String::from("meta"),
+                        }
+                        .into());
+                    }
+                } else {
+                    let actual = std::io::copy(&mut zip.meta()?, &mut std::io::sink())?;
+                    if actual != declared {
+                        return Err(ImperatorErrorKind::MetadataLength { declared, actual }.into());
+                    }
+                }
+            }
+        }
+
+        let gamestate: GamestateStamp = match self.gamestate()? {
+            SaveContentKind::Text(mut x) => x
+                .deserializer()
+                .deserialize()
+                .map_err(ImperatorErrorKind::Deserialize)?,
+            SaveContentKind::Binary(mut x) => x
+                .deserializer(&resolver)
+                .deserialize()
+                .map_err(ImperatorErrorKind::Deserialize)?,
+        };
+
+        // Uncompressed saves without a declared metadata section have nothing
+        // further to compare against
+        if metadata_in_zip || declared > 0 {
+            let meta: MetaStamp = match self.meta()? {
+                SaveMetadataKind::Text(mut x) => x
+                    .deserializer()
+                    .deserialize()
+                    .map_err(ImperatorErrorKind::Deserialize)?,
+                SaveMetadataKind::Binary(mut x) => x
+                    .deserializer(&resolver)
+                    .deserialize()
+                    .map_err(ImperatorErrorKind::Deserialize)?,
+            };
+
+            if meta.version != gamestate.version {
+                return Err(ImperatorErrorKind::MetadataMismatch {
+                    field: "version",
+                    metadata: meta.version.to_string(),
+                    gamestate: gamestate.version.to_string(),
+                }
+                .into());
+            }
+
+            if meta.date != gamestate.date {
+                return Err(ImperatorErrorKind::MetadataMismatch {
+                    field: "date",
+                    metadata: meta.date.game_fmt().to_string(),
+                    gamestate: gamestate.date.game_fmt().to_string(),
+                }
+                .into());
+            }
+        }
+
+        Ok(ValidatedSave {
+            version: gamestate.version,
+            date: gamestate.date,
+            checksum: gamestate.checksum,
+        })
+    }
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Deserializer};
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// A game version like `1.5.3` or `Marius v1.5.3`.
+///
+/// Equality and ordering only consider the numeric components, so
+/// `Marius v1.5.3` is equal to `1.5.3`. The version is formatted as it was
+/// written in the save.
+///
+/// Deserialization accepts versions that aren't recognized rather than
+/// failing, so that a save from a future update can still be read. These
+/// have no numeric components, are only equal to the same version string,
+/// and are ordered after every recognized version.
+#[derive(Debug, Clone)]
+pub struct GameVersion {
+    raw: String,
+    parsed: Option<Parsed>,
+}
+
+#[derive(Debug, Clone)]
+struct Parsed {
+    major: u16,
+    minor: u16,
+    patch: u16,
+    codename: Option<String>,
+}
+
+impl GameVersion {
+    pub fn new(major: u16, minor: u16, patch: u16) -> Self {
+        GameVersion {
+            raw: format!("{}.{}.{}", major, minor, patch),
+            parsed: Some(Parsed {
+                major,
+                minor,
+                patch,
+                codename: None,
+            }),
+        }
+    }
+
+    /// The major component, when the version is recognized
+    pub fn major(&self) -> Option<u16> {
+        self.parsed.as_ref().map(|x| x.major)
+    }
+
+    /// The minor component, when the version is recognized
+    pub fn minor(&self) -> Option<u16> {
+        self.parsed.as_ref().map(|x| x.minor)
+    }
+
+    /// The patch component, when the version is recognized. Defaults to 0
+    /// when the version only has a major and minor component.
+    pub fn patch(&self) -> Option<u16> {
+        self.parsed.as_ref().map(|x| x.patch)
+    }
+
+    /// The name of the update, e.g. `Marius`, when present
+    pub fn codename(&self) -> Option<&str> {
+        self.parsed.as_ref().and_then(|x| x.codename.as_deref())
+    }
+
+    /// The version as written in the save
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Whether the numeric components could be parsed
+    pub fn is_recognized(&self) -> bool {
+        self.parsed.is_some()
+    }
+
+    fn numbers(&self) -> Option<(u16, u16, u16)> {
+        self.parsed.as_ref().map(|x| (x.major, x.minor, x.patch))
+    }
+}
+
+/// The version string could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameVersionParseError {
+    version: String,
+}
+
+impl fmt::Display for GameVersionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unrecognized game version: {}", self.version)
+    }
+}
+
+impl std::error::Error for GameVersionParseError {}
+
+fn parse(s: &str) -> Option<Parsed> {
+    let start = s.find(|c: char| c.is_ascii_digit())?;
+    let codename = s[..start].trim_end_matches('v').trim();
+    let codename = (!codename.is_empty()).then(|| String::from(codename));
+
+    let mut parts = s[start..].split('.');
+    let mut next = |required: bool| match parts.next() {
+        Some(x) => x.parse::<u16>().ok(),
+        None if required => None,
+        None => Some(0),
+    };
+
+    let major = next(true)?;
+    let minor = next(true)?;
+    let patch = next(false)?;
+    Some(Parsed {
+        major,
+        minor,
+        patch,
+        codename,
+    })
+}
+
+/// Strict parsing that errors on versions that aren't recognized
+impl FromStr for GameVersion {
+    type Err = GameVersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let version = GameVersion::from(s);
+        if version.is_recognized() {
+            Ok(version)
+        } else {
+            Err(GameVersionParseError {
+                version: version.raw,
+            })
+        }
+    }
+}
+
+/// Lenient parsing that keeps versions that aren't recognized
+impl From<String> for GameVersion {
+    fn from(raw: String) -> Self {
+        let parsed = parse(&raw);
+        GameVersion { raw, parsed }
+    }
+}
+
+impl From<&str> for GameVersion {
+    fn from(raw: &str) -> Self {
+        GameVersion::from(String::from(raw))
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// Compares the numeric components only
+impl PartialEq for GameVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for GameVersion {}
+
+impl PartialOrd for GameVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GameVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.numbers(), other.numbers()) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => self.raw.cmp(&other.raw),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GameVersion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(GameVersion::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versions() {
+        let version: GameVersion = "1.5.3".parse().unwrap();
+        assert_eq!(version, GameVersion::new(1, 5, 3));
+        assert_eq!(version.codename(), None);
+        assert_eq!(version.to_string(), "1.5.3");
+
+        let version: GameVersion = "Marius v1.5.3".parse().unwrap();
+        assert_eq!(version.codename(), Some("Marius"));
+        assert_eq!(version, GameVersion::new(1, 5, 3));
+        assert_eq!(version.to_string(), "Marius v1.5.3");
+
+        let version: GameVersion = "2.0".parse().unwrap();
+        assert_eq!(version, GameVersion::new(2, 0, 0));
+        assert_eq!(version.patch(), Some(0));
+        assert_eq!(version.to_string(), "2.0");
+        assert!("abc".parse::<GameVersion>().is_err());
+        assert!("1".parse::<GameVersion>().is_err());
+    }
+
+    #[test]
+    fn test_unrecognized_versions() {
+        let version = GameVersion::from("Invictus");
+        assert!(!version.is_recognized());
+        assert_eq!(version.major(), None);
+        assert_eq!(version.raw(), "Invictus");
+        assert_eq!(version.to_string(), "Invictus");
+        assert_eq!(version, GameVersion::from("Invictus"));
+        assert_ne!(version, GameVersion::from("Invictus 2"));
+        assert!(version > GameVersion::new(2, 0, 5));
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        let mut versions = ["2.0.5", "1.4.2", "1.5.3"]
+            .iter()
+            .map(|x| x.parse::<GameVersion>().unwrap())
+            .collect::<Vec<_>>();
+        versions.sort();
+        assert_eq!(
+            versions,
+            vec![
+                GameVersion::new(1, 4, 2),
+                GameVersion::new(1, 5, 3),
+                GameVersion::new(2, 0, 5)
+            ]
+        );
+        assert!(GameVersion::new(1, 10, 0) > GameVersion::new(1, 9, 9));
+    }
+}
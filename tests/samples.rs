@@ -1,20 +1,27 @@
 use core::panic;
 use imperator_save::{
-    models::{GameState, Metadata, Save},
-    BasicTokenResolver, DeserializeImperator, ImperatorBinaryDeserialization, ImperatorFile,
-    ImperatorMelt, JominiFileKind, MeltOptions, SaveDataKind, SaveHeaderKind, SaveMetadataKind,
+    models::{AchievementEligibility, GameState, Ineligible, Metadata, Save},
+    CampaignTimeline, ChangeKind, DateDetection, DateFormat, DeserializeImperator,
+    FailedResolveStrategy, FieldAction, FieldSchema, GameVersion, GamestateSections,
+    ImperatorBinaryDeserialization, ImperatorDate, ImperatorErrorKind, ImperatorFile,
+    ImperatorMelt, ImperatorMetadata, ImperatorParallelDeserialize, ImperatorParallelMelt,
+    ImperatorRedact, ImperatorValidate, JominiFileKind, MeltOptions, PdsDate, PlayerSummary,
+    RedactOptions, SaveDataKind, SaveDiff, SaveHeaderKind, SaveMetadataKind, SaveSummary,
+    TokenInference, TokenTable,
 };
-use jomini::binary::TokenResolver;
 use std::{
+    collections::HashMap,
     io::{Cursor, Read},
     sync::LazyLock,
 };
 
+mod synthetic;
 mod utils;
 
-static TOKENS: LazyLock<BasicTokenResolver> = LazyLock::new(|| {
-    let file_data = std::fs::read("assets/imperator.txt").unwrap_or_default();
-    BasicTokenResolver::from_text_lines(file_data.as_slice()).unwrap()
+static TOKENS: LazyLock<TokenTable> = LazyLock::new(|| match TokenTable::from_env() {
+    Ok(tokens) => tokens,
+    Err(e) if matches!(e.kind(), ImperatorErrorKind::TokensNotFound { .. }) => TokenTable::new(),
+    Err(e) => panic!("{}", e),
 });
 
 macro_rules! skip_if_no_tokens {
@@ -37,7 +44,7 @@ fn test_debug_save() {
     };
 
     let save: Metadata = text.deserializer().deserialize().unwrap();
-    assert_eq!(save.version, String::from("1.4.2"));
+    assert_eq!(save.version, GameVersion::new(1, 4, 2));
 
     let save: GameState = text.deserializer().deserialize().unwrap();
     assert_eq!(save.speed, 2);
@@ -60,10 +67,10 @@ fn test_observer_save() {
     }
     .unwrap();
 
-    assert_eq!(save.version, String::from("1.5.3"));
+    assert_eq!(save.version, GameVersion::new(1, 5, 3));
 
     let save: Save = (&file).deserialize(&*TOKENS).unwrap();
-    assert_eq!(save.meta.version, String::from("1.5.3"));
+    assert_eq!(save.meta.version, GameVersion::new(1, 5, 3));
 }
 
 #[test]
@@ -100,10 +107,10 @@ fn test_patch_20() {
         SaveMetadataKind::Binary(mut x) => x.deserializer(&*TOKENS).deserialize(),
     }
     .unwrap();
-    assert_eq!(save.version, String::from("2.0.5"));
+    assert_eq!(save.version, GameVersion::new(2, 0, 5));
 
     let save: Save = (&file).deserialize(&*TOKENS).unwrap();
-    assert_eq!(save.meta.version, String::from("2.0.5"));
+    assert_eq!(save.meta.version, GameVersion::new(2, 0, 5));
 }
 
 #[test]
@@ -124,10 +131,10 @@ fn test_patch_20_slice() {
         SaveMetadataKind::Binary(mut x) => x.deserializer(&*TOKENS).deserialize(),
     }
     .unwrap();
-    assert_eq!(save.version, String::from("2.0.5"));
+    assert_eq!(save.version, GameVersion::new(2, 0, 5));
 
     let save: Save = (&file).deserialize(&*TOKENS).unwrap();
-    assert_eq!(save.meta.version, String::from("2.0.5"));
+    assert_eq!(save.meta.version, GameVersion::new(2, 0, 5));
 }
 
 #[test]
@@ -137,7 +144,7 @@ fn test_non_ascii_save() {
     let file = ImperatorFile::from_file(file).unwrap();
     let save: Save = (&file).deserialize(&*TOKENS).unwrap();
     assert_eq!(file.header().kind(), SaveHeaderKind::UnifiedBinary);
-    assert_eq!(save.meta.version, String::from("1.5.3"));
+    assert_eq!(save.meta.version, GameVersion::new(1, 5, 3));
 }
 
 #[test]
@@ -157,7 +164,7 @@ fn test_roundtrip_header_melt() {
     };
     let meta: Metadata = text.deserializer().deserialize().unwrap();
 
-    assert_eq!(meta.version, String::from("1.5.3"));
+    assert_eq!(meta.version, GameVersion::new(1, 5, 3));
 }
 
 #[test]
@@ -172,3 +179,1339 @@ fn test_header_melt() {
     (&file).melt(options, &*TOKENS, &mut out).unwrap();
     assert_eq!(&melted[..], out.get_ref());
 }
+
+#[test]
+fn test_melt_seekable() {
+    for data in [
+        synthetic::binary(),
+        synthetic::zip(SaveHeaderKind::UnifiedBinary),
+        synthetic::zip(SaveHeaderKind::SplitBinary),
+    ] {
+        let file = ImperatorFile::from_slice(&data).unwrap();
+        let mut expected = Vec::new();
+        let expected_doc = (&file)
+            .melt(MeltOptions::new(), &*synthetic::TOKENS, &mut expected)
+            .unwrap();
+
+        let mut out = Cursor::new(Vec::new());
+        let doc = (&file)
+            .melt_seekable(MeltOptions::new(), &*synthetic::TOKENS, &mut out)
+            .unwrap();
+        assert_eq!(&expected, out.get_ref());
+        assert_eq!(doc.output_len(), expected_doc.output_len());
+
+        let melted = ImperatorFile::from_slice(out.get_ref()).unwrap();
+        let save: Save = (&melted).deserialize(&*synthetic::TOKENS).unwrap();
+        assert_eq!(save.meta.meta_player_name.as_deref(), Some("Rome"));
+        assert_eq!(save.gamestate.speed, 3);
+    }
+}
+
+#[test]
+fn test_header_melt_schema_dates() {
+    skip_if_no_tokens!();
+    let data = include_bytes!("fixtures/header");
+    let melted = include_bytes!("fixtures/header.melted");
+    let file = ImperatorFile::from_slice(&data[..]).unwrap();
+
+    let mut out = Vec::new();
+    let options =
+        MeltOptions::new().date_detection(DateDetection::Schema(FieldSchema::imperator()));
+    (&file).melt(options, &*TOKENS, &mut out).unwrap();
+    assert_eq!(&melted[..], &out[..]);
+
+    let mut out = Vec::new();
+    let numbers = vec![String::from("date")];
+    let schema = FieldSchema::new(["birth_date"], numbers);
+    let options = MeltOptions::new().date_detection(DateDetection::Schema(schema));
+    let doc = (&file).melt(options, &*TOKENS, &mut out).unwrap();
+    assert_eq!(doc.dates_detected(), 0);
+    assert!(!String::from_utf8(out).unwrap().contains("date=450.10.1"));
+}
+
+#[test]
+fn test_melt_date_detection() {
+    use jomini::binary::Token;
+
+    let resolver: HashMap<u16, String> = [(0x2000, "seed"), (0x2001, "history")]
+        .into_iter()
+        .map(|(id, token)| (id, String::from(token)))
+        .collect();
+    let date = ImperatorDate::parse("450.10.1").unwrap().to_binary();
+    let save = synthetic::binary_save(&[
+        Token::Id(0x2000),
+        Token::Equal,
+        Token::I32(date),
+        Token::Id(0x2001),
+        Token::Equal,
+        Token::Open,
+        Token::I32(date),
+        Token::Close,
+        Token::Id(0x2000),
+        Token::Equal,
+        Token::Open,
+        Token::I32(date),
+        Token::Close,
+    ]);
+    let file = ImperatorFile::from_slice(&save).unwrap();
+
+    // Only the value of the seed field is exempt from the heuristic
+    let mut out = Vec::new();
+    (&file)
+        .melt(MeltOptions::new().compact(true), &resolver, &mut out)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(&format!(
+        "seed={} history={{ 450.10.1 }} seed={{ 450.10.1 }}",
+        date
+    )));
+
+    // Schemas can be built at runtime and apply to array elements
+    let numbers: Vec<String> = vec![String::from("history")];
+    let schema = FieldSchema::new(["seed"], numbers);
+    let options = MeltOptions::new()
+        .compact(true)
+        .date_detection(DateDetection::Schema(schema));
+    let mut out = Vec::new();
+    (&file).melt(options, &resolver, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(&format!(
+        "seed=450.10.1 history={{ {} }} seed={{ 450.10.1 }}",
+        date
+    )));
+}
+
+#[test]
+fn test_melt_formatting() {
+    let data = synthetic::binary();
+    let file = ImperatorFile::from_slice(&data).unwrap();
+
+    // The metadata and the gamestate each occupy a single line
+    let mut out = Vec::new();
+    let options = MeltOptions::new().compact(true);
+    (&file)
+        .melt(options, &*synthetic::TOKENS, &mut out)
+        .unwrap();
+
+    let melted = ImperatorFile::from_slice(&out).unwrap();
+    let header_len = melted.header().header_len();
+    let body = std::str::from_utf8(&out[header_len..]).unwrap();
+    assert_eq!(body.trim_end().lines().count(), 2);
+    assert!(body.contains(r#"played_country={ player_name="Gaius" country=1 }"#));
+
+    let save: Save = (&melted).deserialize(&*synthetic::TOKENS).unwrap();
+    assert_eq!(save.meta.date.game_fmt().to_string(), "460.3.12");
+    assert_eq!(save.gamestate.speed, 3);
+
+    let mut out = Vec::new();
+    let options = MeltOptions::new()
+        .indent_char(b' ')
+        .indent_width(2)
+        .float_precision(Some(2))
+        .date_format(DateFormat::Iso8601);
+    (&file)
+        .melt(options, &*synthetic::TOKENS, &mut out)
+        .unwrap();
+    let body = std::str::from_utf8(&out).unwrap();
+    assert!(body.contains("date=0460-03-12"));
+    assert!(body.contains("\n  database={\n    1={\n"));
+    assert!(body.contains("gold=105.50"));
+    assert!(!body.contains('\t'));
+}
+
+#[test]
+fn test_header_melt_field_filter() {
+    skip_if_no_tokens!();
+    let data = include_bytes!("fixtures/header");
+    let file = ImperatorFile::from_slice(&data[..]).unwrap();
+
+    let options = MeltOptions::new().field_filter(|field| match field.path().as_str() {
+        "meta_player_name" => FieldAction::Redact(String::from("anonymous")),
+        "enabled_mods" => FieldAction::Drop,
+        "sync_ai_tasks" => FieldAction::Rename(String::from("ai_tasks")),
+        _ => FieldAction::Keep,
+    });
+    let mut out = Vec::new();
+    (&file).melt(options, &*TOKENS, &mut out).unwrap();
+
+    let melted = ImperatorFile::from_slice(&out).unwrap();
+    let JominiFileKind::Uncompressed(SaveDataKind::Text(text)) = melted.kind() else {
+        panic!("Expected a text file");
+    };
+    let meta: Metadata = text.deserializer().deserialize().unwrap();
+    assert_eq!(meta.meta_player_name.as_deref(), Some("anonymous"));
+
+    let body = std::str::from_utf8(&out).unwrap();
+    assert!(!body.contains("Suionia"));
+    assert!(!body.contains("enabled_mods"));
+    assert!(body.contains("ai_tasks={"));
+    assert!(!body.contains("sync_ai_tasks={"));
+}
+
+#[test]
+fn test_melt_field_filter() {
+    let gamestate = format!("{}flags={{ tag gold }}\n", synthetic::GAMESTATE);
+    let data = synthetic::binary_with(&gamestate);
+    let file = ImperatorFile::from_slice(&data).unwrap();
+
+    // The first field of an object is filtered like any other
+    let options =
+        MeltOptions::new()
+            .compact(true)
+            .field_filter(|field| match field.path().as_str() {
+                "meta_player_name" | "played_country.player_name" => {
+                    FieldAction::Redact(String::from("anonymous"))
+                }
+                "country.database.1.tag" => FieldAction::Rename(String::from("country_tag")),
+                "game_rules.difficulty" | "country.database.1.currency_data.manpower" => {
+                    FieldAction::Drop
+                }
+                _ => FieldAction::Keep,
+            });
+    assert_eq!(options.clone(), options);
+    assert_ne!(options, MeltOptions::new().compact(true));
+
+    let mut out = Vec::new();
+    (&file)
+        .melt(options, &*synthetic::TOKENS, &mut out)
+        .unwrap();
+    let body = std::str::from_utf8(&out).unwrap();
+    assert!(body.contains(r#"meta_player_name="anonymous""#));
+    assert!(body.contains(r#"played_country={ player_name="anonymous" country=1 }"#));
+    assert!(body.contains("game_rules={ }"));
+    assert!(body.contains(r#"1={ country_tag="ROM" currency_data={ gold=105.5 stability=50 } }"#));
+    assert!(body.contains(r#"3={ tag="CAR" }"#));
+    assert!(body.contains("flags={ tag gold }"));
+    assert!(!body.contains("Gaius"));
+}
+
+#[test]
+fn test_melt_field_filter_gamestate_start() {
+    let data = synthetic::binary();
+    let file = ImperatorFile::from_slice(&data).unwrap();
+    let cases = [
+        (FieldAction::Drop, "\ngame_rules={"),
+        (
+            FieldAction::Rename(String::from("game_speed")),
+            "\ngame_speed=3\n",
+        ),
+        (
+            FieldAction::Redact(String::from("fast")),
+            "\nspeed=\"fast\"\n",
+        ),
+    ];
+
+    for (action, expected) in cases {
+        let options = MeltOptions::new().field_filter(move |field| match field.path().as_str() {
+            "speed" | "played_country" => action.clone(),
+            _ => FieldAction::Keep,
+        });
+
+        let mut out = Vec::new();
+        (&file)
+            .melt(options.clone(), &*synthetic::TOKENS, &mut out)
+            .unwrap();
+        let body = std::str::from_utf8(&out).unwrap();
+        assert!(body.contains(expected), "{}", body);
+        assert!(!body.contains("\nspeed=3"));
+
+        let mut seekable = Cursor::new(Vec::new());
+        (&file)
+            .melt_seekable(options.clone(), &*synthetic::TOKENS, &mut seekable)
+            .unwrap();
+        assert_eq!(seekable.get_ref(), &out);
+
+        for threads in 1..=4 {
+            let mut parallel = Vec::new();
+            (&file)
+                .melt_parallel(options.clone(), &*synthetic::TOKENS, &mut parallel, threads)
+                .unwrap();
+            assert_eq!(
+                std::str::from_utf8(&parallel).unwrap(),
+                body,
+                "{} threads",
+                threads
+            );
+        }
+    }
+}
+
+#[test]
+fn test_header_melt_diagnostics() {
+    let data = include_bytes!("fixtures/header");
+    let file = ImperatorFile::from_slice(&data[..]).unwrap();
+
+    let resolver: HashMap<u16, String> = HashMap::new();
+    let mut out = Vec::new();
+    let doc = (&file)
+        .melt(MeltOptions::new(), &resolver, &mut out)
+        .unwrap();
+
+    assert_eq!(doc.output_len(), out.len() as u64);
+    assert_eq!(doc.skipped_fields().count(), 10);
+    assert!(doc.skipped_fields().all(|x| x.path() == "<unknown>"));
+    assert_eq!(doc.token_counts().id, 10);
+    assert_eq!(doc.token_counts().open, 3);
+    assert_eq!(doc.dates_detected(), 0);
+    assert!(doc.unknown_tokens().is_empty());
+}
+
+fn melted_metadata(data: &[u8]) -> Metadata {
+    let file = ImperatorFile::from_slice(data).unwrap();
+    let mut out = Vec::new();
+    (&file)
+        .melt(MeltOptions::new(), &*TOKENS, &mut out)
+        .unwrap();
+
+    let file = ImperatorFile::from_slice(&out).unwrap();
+    let JominiFileKind::Uncompressed(SaveDataKind::Text(text)) = file.kind() else {
+        panic!("Expected a text file");
+    };
+    let meta = text.deserializer().deserialize().unwrap();
+    meta
+}
+
+#[test]
+fn test_redact_header() {
+    skip_if_no_tokens!();
+    let data = include_bytes!("fixtures/header");
+    let options = RedactOptions::new();
+
+    // binary
+    let mut binary = Vec::new();
+    let replaced = (&data[..]).redact(&options, &*TOKENS, &mut binary).unwrap();
+    assert_eq!(replaced, 1);
+    let meta = melted_metadata(&binary);
+    assert_eq!(meta.meta_player_name.as_deref(), Some("Anonymous"));
+    assert_eq!(meta.version, GameVersion::new(1, 5, 3));
+
+    // text
+    let file = ImperatorFile::from_slice(&data[..]).unwrap();
+    let mut melted = Vec::new();
+    (&file)
+        .melt(MeltOptions::new(), &*TOKENS, &mut melted)
+        .unwrap();
+    let mut text = Vec::new();
+    let resolver: HashMap<u16, String> = HashMap::new();
+    (&melted[..])
+        .redact(&options, &resolver, &mut text)
+        .unwrap();
+    let meta = melted_metadata(&text);
+    assert_eq!(meta.meta_player_name.as_deref(), Some("Anonymous"));
+    assert_eq!(
+        text.len() - melted.len(),
+        "Anonymous".len() - "Suionia".len()
+    );
+
+    // zip
+    let body = &data[file.header().header_len()..];
+    let meta = &body[..file.header().metadata_len() as usize];
+    let zipped = synthetic::zipped_save(
+        synthetic::header(SaveHeaderKind::UnifiedBinary, 0),
+        meta,
+        &[("gamestate", body)],
+    );
+
+    let mut out = Vec::new();
+    let replaced = (&zipped[..]).redact(&options, &*TOKENS, &mut out).unwrap();
+    assert_eq!(replaced, 2);
+    let meta = melted_metadata(&out);
+    assert_eq!(meta.meta_player_name.as_deref(), Some("Anonymous"));
+}
+
+#[test]
+fn test_redact_zip_entries() {
+    let meta = synthetic::encode(synthetic::META);
+    let gamestate = synthetic::encode(&format!("{}{}", synthetic::META, synthetic::GAMESTATE));
+    let zipped = synthetic::zipped_save(
+        synthetic::header(SaveHeaderKind::SplitBinary, 0),
+        b"",
+        &[
+            ("meta", &meta),
+            ("readme.txt", b"keep me"),
+            ("gamestate", &gamestate),
+        ],
+    );
+
+    let mut out = Vec::new();
+    let replaced = (&zipped[..])
+        .redact(&RedactOptions::new(), &*synthetic::TOKENS, &mut out)
+        .unwrap();
+    assert_eq!(replaced, 3);
+
+    let archive = rawzip::ZipArchive::from_slice(&out).unwrap();
+    let mut names = Vec::new();
+    let mut entries = archive.entries();
+    while let Some(entry) = entries.next_entry().unwrap() {
+        names.push(String::from_utf8_lossy(entry.file_path().as_ref()).into_owned());
+    }
+    assert_eq!(names, ["meta", "readme.txt", "gamestate"]);
+
+    let file = ImperatorFile::from_slice(&out).unwrap();
+    let JominiFileKind::Zip(zip) = file.kind() else {
+        panic!("Expected a zip file");
+    };
+    let mut extra = Vec::new();
+    zip.read_entry("readme.txt")
+        .unwrap()
+        .read_to_end(&mut extra)
+        .unwrap();
+    assert_eq!(extra, b"keep me");
+
+    let summary = SaveSummary::from_slice(&out, &*synthetic::TOKENS).unwrap();
+    assert_eq!(summary.country_name(), Some("Anonymous"));
+    let player = PlayerSummary::from_file(&file, &*synthetic::TOKENS).unwrap();
+    assert_eq!(player.name(), Some("Anonymous 1"));
+    assert_eq!(player.tag(), Some("ROM"));
+}
+
+#[test]
+fn test_redact_unresolved() {
+    let data = synthetic::binary();
+    let redact = |options: &RedactOptions, resolver: &HashMap<u16, String>| {
+        let err = (&data[..])
+            .redact(options, resolver, &mut Vec::new())
+            .unwrap_err();
+        match err.kind() {
+            ImperatorErrorKind::UnresolvedRedaction { key } => key.clone(),
+            _ => panic!("unexpected error: {}", err),
+        }
+    };
+
+    let tokens: HashMap<u16, String> = synthetic::TOKENS
+        .iter()
+        .map(|(id, token)| (id, String::from(token)))
+        .collect();
+    let options = RedactOptions::new();
+    assert_eq!(redact(&options, &HashMap::new()), "meta_player_name");
+
+    let mut partial = tokens.clone();
+    partial.retain(|_, token| token != "meta_player_name");
+    assert_eq!(redact(&options, &partial), "meta_player_name");
+
+    let options = RedactOptions::new().field("tag").field("nickname");
+    assert_eq!(redact(&options, &tokens), "nickname");
+
+    let options = RedactOptions::new().field("tag");
+    let mut out = Vec::new();
+    let replaced = (&data[..]).redact(&options, &tokens, &mut out).unwrap();
+    assert_eq!(replaced, 4);
+
+    // Text saves don't need a resolver
+    let text = synthetic::text();
+    let replaced = (&text[..])
+        .redact(&options, HashMap::<u16, String>::new(), &mut out)
+        .unwrap();
+    assert_eq!(replaced, 4);
+}
+
+#[test]
+fn test_diff_text() {
+    let old = br#"
+date=450.10.1
+character={
+    database={
+        1={ name="Marcus" wealth=10.5 }
+        2={ name="Titus" wealth=3 }
+    }
+}
+ai_task={ interval=1 }
+ai_task={ interval=5 }
+color=rgb { 10 20 30 }
+"#;
+    let new = br#"
+date=451.1.1
+character={
+    database={
+        2={ name="Titus" wealth=3 }
+        1={ name="Marcus" wealth=12 }
+        3={ name="Gaius" }
+    }
+}
+ai_task={ interval=1 }
+color=rgb { 10 20 30 }
+speed=2
+"#;
+
+    let diff = SaveDiff::from_text(old, new).unwrap();
+    let changes = diff
+        .changes()
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        vec![
+            "~ date: 450.10.1 -> 451.1.1",
+            "~ character.database.1.wealth: 10.5 -> 12",
+            "+ character.database.3 = { ... }",
+            "- ai_task[1] = { ... }",
+            "+ speed = 2",
+        ]
+    );
+    assert_eq!(diff.changes()[1].section(), "character");
+    assert_eq!(diff.changes()[3].kind(), ChangeKind::Removed);
+}
+
+#[test]
+fn test_diff_same_file() {
+    let data = include_bytes!("fixtures/header");
+    let file = ImperatorFile::from_slice(&data[..]).unwrap();
+    let diff = SaveDiff::from_files(&file, &file, &*TOKENS).unwrap();
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn test_campaign_timeline() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let write_save = |name: &str, body: &str| {
+        let save = synthetic::save(SaveHeaderKind::Text, 0, body.as_bytes());
+        std::fs::write(dir.join(name), save).unwrap();
+    };
+
+    write_save(
+        "later.rome",
+        r#"date=460.1.1 random_seed=42 country={ database={
+            0={ tag="ROM" currency_data={ gold=250.5 manpower=4 } }
+            1=none
+        } }"#,
+    );
+    write_save(
+        "earlier.rome",
+        r#"date=450.10.1 random_seed=42 country={ database={
+            0={ tag="ROM" currency_data={ gold=100 manpower=3 } }
+            1={ tag="CAR" currency_data={ gold=80 } }
+        } }"#,
+    );
+    std::fs::write(dir.join("notes.txt"), "not a save").unwrap();
+
+    let resolver: HashMap<u16, String> = HashMap::new();
+    let timeline = CampaignTimeline::from_dir(dir, &resolver).unwrap();
+    assert_eq!(timeline.random_seed(), Some(42));
+    let dates = timeline
+        .entries()
+        .iter()
+        .map(|x| x.date().game_fmt().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(dates, vec!["450.10.1", "460.1.1"]);
+
+    let gold = timeline
+        .country("ROM")
+        .map(|(_, x)| x.gold)
+        .collect::<Vec<_>>();
+    assert_eq!(gold, vec![100.0, 250.5]);
+    assert_eq!(timeline.country("CAR").count(), 1);
+
+    for (name, body) in [
+        ("other.rome", "date=455.1.1 random_seed=7"),
+        ("unseeded.rome", "date=455.1.1"),
+    ] {
+        write_save(name, body);
+        let err = CampaignTimeline::from_dir(dir, &resolver).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ImperatorErrorKind::PlaythroughMismatch { .. }
+        ));
+        std::fs::remove_file(dir.join(name)).unwrap();
+    }
+}
+
+#[test]
+fn test_validate() {
+    let resolver: HashMap<u16, String> = HashMap::new();
+
+    let meta = "version=\"1.5.3\"\ndate=450.10.1\n";
+    let body = format!("{}checksum=\"abc\"\n", meta);
+    let text = synthetic::save(SaveHeaderKind::Text, meta.len(), body.as_bytes());
+
+    let file = ImperatorFile::from_slice(&text).unwrap();
+    let validated = (&file).validate(&resolver).unwrap();
+    assert_eq!(validated.version(), &GameVersion::new(1, 5, 3));
+    assert_eq!(validated.date().game_fmt().to_string(), "450.10.1");
+    assert_eq!(validated.checksum(), Some("abc"));
+
+    // metadata length beyond the end of the body
+    let tampered = synthetic::save(SaveHeaderKind::Text, text.len(), body.as_bytes());
+    let file = ImperatorFile::from_slice(&tampered).unwrap();
+    let err = (&file).validate(&resolver).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ImperatorErrorKind::MetadataLength { .. }
+    ));
+
+    // inlined metadata that disagrees with the zipped gamestate
+    let stale = "version=\"1.5.2\"\ndate=450.10.1\n";
+    let zipped = synthetic::zipped_save(
+        synthetic::header(SaveHeaderKind::UnifiedText, 0),
+        stale.as_bytes(),
+        &[("gamestate", meta.as_bytes())],
+    );
+
+    let file = ImperatorFile::from_slice(&zipped).unwrap();
+    let err = (&file).validate(&resolver).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ImperatorErrorKind::MetadataMismatch {
+            field: "version",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_achievement_eligibility() {
+    let parse = |body: &str| -> Save {
+        let body = format!(
+            "version=\"1.5.3\" date=450.10.1 enabled_dlcs={{ }} play_time=1 speed=1\n{}",
+            body
+        );
+        let save = synthetic::save(SaveHeaderKind::Text, 0, body.as_bytes());
+        let file = ImperatorFile::from_slice(&save).unwrap();
+        let resolver: HashMap<u16, String> = HashMap::new();
+        (&file).deserialize(&resolver).unwrap()
+    };
+
+    let save = parse("ironman=yes iron=yes game_rules={ difficulty=normal }");
+    assert!(save
+        .achievement_eligibility(SaveHeaderKind::UnifiedBinary)
+        .is_eligible());
+    assert_eq!(
+        save.achievement_eligibility(SaveHeaderKind::Text),
+        AchievementEligibility::Ineligible(Ineligible::TextSave {
+            kind: SaveHeaderKind::Text
+        })
+    );
+
+    let save = parse("ironman=no");
+    assert_eq!(
+        save.achievement_eligibility(SaveHeaderKind::UnifiedBinary),
+        AchievementEligibility::Ineligible(Ineligible::NotIronman)
+    );
+
+    // Either ironman flag on its own is enough
+    for flags in ["ironman=yes", "iron=yes", "ironman=no iron=yes"] {
+        let save = parse(flags);
+        assert!(save
+            .achievement_eligibility(SaveHeaderKind::UnifiedBinary)
+            .is_eligible());
+    }
+
+    // Without game rules, the difficulty is assumed to be normal
+    let save = parse("ironman=yes");
+    assert_eq!(save.gamestate.game_rules, None);
+    assert!(save
+        .achievement_eligibility(SaveHeaderKind::UnifiedBinary)
+        .is_eligible());
+
+    let save = parse("ironman=yes iron=yes enabled_mods={ \"mod/ui.mod\" }");
+    assert_eq!(
+        save.achievement_eligibility(SaveHeaderKind::UnifiedBinary),
+        AchievementEligibility::Ineligible(Ineligible::Modded {
+            mods: vec![String::from("mod/ui.mod")]
+        })
+    );
+
+    let save = parse("ironman=yes iron=yes game_rules={ difficulty=very_easy }");
+    assert_eq!(
+        save.achievement_eligibility(SaveHeaderKind::UnifiedBinary),
+        AchievementEligibility::Ineligible(Ineligible::Difficulty {
+            difficulty: String::from("very_easy")
+        })
+    );
+}
+
+#[cfg(feature = "embedded-tokens")]
+#[test]
+fn test_embedded_tokens() {
+    use imperator_save::EmbeddedTokens;
+    use jomini::binary::TokenResolver;
+
+    let tokens = TokenTable::from_path(env!("IMPERATOR_TOKENS")).unwrap();
+    let embedded = EmbeddedTokens::new();
+    assert!(!embedded.is_empty());
+    for (id, token) in tokens.iter() {
+        assert_eq!(embedded.resolve(id), Some(token));
+    }
+    assert_eq!(embedded.resolve(u16::MAX), tokens.get(u16::MAX));
+}
+
+#[test]
+fn test_infer_tokens() {
+    let mut binary = Vec::new();
+    let tokens = [
+        jomini::binary::Token::Id(0x00ee),
+        jomini::binary::Token::Equal,
+        jomini::binary::Token::Quoted(jomini::Scalar::new(b"1.5.3")),
+        jomini::binary::Token::Id(0x2001),
+        jomini::binary::Token::Equal,
+        jomini::binary::Token::Open,
+        jomini::binary::Token::Id(0x2002),
+        jomini::binary::Token::Equal,
+        jomini::binary::Token::I32(10),
+        jomini::binary::Token::Id(0x2003),
+        jomini::binary::Token::Close,
+    ];
+    for token in &tokens {
+        token.write(&mut binary).unwrap();
+    }
+
+    let text = b"version=\"1.5.3\"\ntreasury={\n\tgold=10\n\tsilver\n}\n";
+    let mut known = TokenTable::new();
+    known.insert(0x00ee, "version");
+    known.insert(0x2003, "bronze");
+
+    let inference = TokenInference::from_slices(&binary, text, &known).unwrap();
+    let inferred = inference.inferred().collect::<Vec<_>>();
+    assert_eq!(inferred, vec![(0x2001, "treasury"), (0x2002, "gold")]);
+    assert_eq!(inference.conflicts().len(), 1);
+    assert_eq!(inference.conflicts()[0].id(), 0x2003);
+    assert_eq!(inference.conflicts()[0].existing(), "bronze");
+    assert_eq!(inference.conflicts()[0].inferred(), "silver");
+    assert_eq!(inference.tokens().len(), 4);
+
+    let err =
+        TokenInference::from_slices(&binary, b"version=\"1.5.3\" treasury=5", &known).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ImperatorErrorKind::TokenAlignment { .. }
+    ));
+}
+
+#[test]
+fn test_infer_tokens_header() {
+    skip_if_no_tokens!();
+    let data = include_bytes!("fixtures/header");
+    let file = ImperatorFile::from_slice(&data[..]).unwrap();
+    let mut melted = Vec::new();
+    (&file)
+        .melt(MeltOptions::new().verbatim(true), &*TOKENS, &mut melted)
+        .unwrap();
+    let text = ImperatorFile::from_slice(&melted).unwrap();
+
+    let inference = TokenInference::from_files(&file, &text, &TokenTable::new()).unwrap();
+    assert!(inference.conflicts().is_empty());
+    for (id, token) in inference.tokens().iter() {
+        assert_eq!(TOKENS.get(id), Some(token));
+    }
+    assert_eq!(inference.tokens().get(0x00ee), Some("version"));
+}
+
+#[test]
+fn test_infer_tokens_wrong_encoding() {
+    let text = synthetic::save(SaveHeaderKind::Text, 0, b"date=450.1.1");
+    let text = ImperatorFile::from_slice(&text).unwrap();
+    let err = TokenInference::from_files(&text, &text, &TokenTable::new()).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ImperatorErrorKind::InferenceEncoding {
+            expected: "binary",
+            actual: "text"
+        }
+    ));
+}
+
+#[test]
+fn test_melt_parallel() {
+    use jomini::binary::Token;
+
+    let resolver: HashMap<u16, String> = [
+        (0x00ee, "version"),
+        (0x2000, "speed"),
+        (0x2001, "date"),
+        (0x2002, "family"),
+        (0x2003, "gold"),
+        (0x2004, "name"),
+        (0x2005, "players"),
+        (0x2006, "color"),
+        (0x2007, "hsv"),
+    ]
+    .into_iter()
+    .map(|(id, token)| (id, String::from(token)))
+    .collect();
+
+    let mut tokens = vec![
+        Token::Id(0x00ee),
+        Token::Equal,
+        Token::Quoted(jomini::Scalar::new(b"1.5.3")),
+        Token::Id(0x2000),
+        Token::Equal,
+        Token::I32(3),
+    ];
+    for i in 0..200 {
+        tokens.extend([
+            Token::Id(0x2002),
+            Token::Equal,
+            Token::Open,
+            Token::Id(0x2004),
+            Token::Equal,
+            Token::Quoted(jomini::Scalar::new(b"Julii")),
+            Token::Id(0x2003),
+            Token::Equal,
+            Token::F32([0, 0, 0x80, 0x3f]),
+            Token::Id(0x2001),
+            Token::Equal,
+            Token::I32(43_808_760 + i),
+            Token::Id(0x3000),
+            Token::Equal,
+            Token::U32(5),
+            Token::Close,
+            Token::Id(0x2005),
+            Token::Equal,
+            Token::Open,
+            Token::U32(1),
+            Token::Id(0x3001),
+            Token::Close,
+            Token::Id(0x3002),
+            Token::Equal,
+            Token::U32(1),
+        ]);
+    }
+
+    // Not a plain top level field, so no further splits are made
+    tokens.extend([
+        Token::Id(0x2006),
+        Token::Equal,
+        Token::Id(0x2007),
+        Token::Open,
+        Token::F32([0, 0, 0, 0x3f]),
+        Token::Close,
+        Token::Id(0x2002),
+        Token::Equal,
+        Token::Open,
+        Token::Close,
+    ]);
+
+    let save = synthetic::binary_save(&tokens);
+    let file = ImperatorFile::from_slice(&save).unwrap();
+    for options in [
+        MeltOptions::new(),
+        MeltOptions::new().compact(true),
+        MeltOptions::new().unresolved_paths(true),
+    ] {
+        let mut expected = Vec::new();
+        let expected_doc = (&file)
+            .melt(options.clone(), &resolver, &mut expected)
+            .unwrap();
+
+        for threads in 1..=5 {
+            let mut actual = Vec::new();
+            let doc = (&file)
+                .melt_parallel(options.clone(), &resolver, &mut actual, threads)
+                .unwrap();
+            assert_eq!(
+                std::str::from_utf8(&actual).unwrap(),
+                std::str::from_utf8(&expected).unwrap()
+            );
+            assert_eq!(doc.output_len(), expected_doc.output_len());
+            assert_eq!(doc.token_counts(), expected_doc.token_counts());
+            assert_eq!(doc.dates_detected(), expected_doc.dates_detected());
+            assert_eq!(doc.unknown_tokens(), expected_doc.unknown_tokens());
+            assert_eq!(doc.unresolved_fields(), expected_doc.unresolved_fields());
+        }
+    }
+}
+
+#[test]
+fn test_melt_unresolved_paths() {
+    let resolver: HashMap<u16, String> = synthetic::TOKENS
+        .iter()
+        .filter(|(_, token)| *token != "gold")
+        .map(|(id, token)| (id, String::from(token)))
+        .collect();
+    let data = synthetic::binary();
+    let file = ImperatorFile::from_slice(&data).unwrap();
+
+    let mut out = Vec::new();
+    let doc = (&file)
+        .melt(MeltOptions::new(), &resolver, &mut out)
+        .unwrap();
+    let paths: Vec<_> = doc.unresolved_fields().iter().map(|x| x.path()).collect();
+    assert_eq!(paths, ["<unknown>"]);
+
+    let options = MeltOptions::new().unresolved_paths(true);
+    let doc = (&file).melt(options, &resolver, &mut out).unwrap();
+    let paths: Vec<_> = doc.unresolved_fields().iter().map(|x| x.path()).collect();
+    assert_eq!(paths, ["country.database.1.currency_data.<unknown>"]);
+}
+
+#[test]
+fn test_deserialize_databases() {
+    use jomini::binary::Token;
+
+    let resolver: HashMap<u16, String> = [
+        (0x2000, "speed"),
+        (0x2001, "country"),
+        (0x2002, "character"),
+        (0x2003, "population"),
+        (0x2004, "provinces"),
+        (0x2005, "database"),
+        (0x2006, "tag"),
+        (0x2007, "culture"),
+        (0x2008, "type"),
+        (0x2009, "owner"),
+        (0x200a, "played_country"),
+    ]
+    .into_iter()
+    .map(|(id, token)| (id, String::from(token)))
+    .collect();
+
+    let database = |section: u16, entries: &[(u32, Vec<Token<'static>>)]| {
+        let mut tokens = vec![
+            Token::Id(section),
+            Token::Equal,
+            Token::Open,
+            Token::Id(0x2005),
+            Token::Equal,
+            Token::Open,
+        ];
+        for (id, fields) in entries {
+            tokens.extend([Token::U32(*id), Token::Equal]);
+            if fields.is_empty() {
+                tokens.push(Token::Unquoted(jomini::Scalar::new(b"none")));
+            } else {
+                tokens.push(Token::Open);
+                tokens.extend(fields.iter().cloned());
+                tokens.push(Token::Close);
+            }
+        }
+        tokens.extend([Token::Close, Token::Close]);
+        tokens
+    };
+
+    let field = |key: u16, value: &'static [u8]| {
+        [
+            Token::Id(key),
+            Token::Equal,
+            Token::Quoted(jomini::Scalar::new(value)),
+        ]
+    };
+
+    let mut tokens = vec![Token::Id(0x2000), Token::Equal, Token::I32(3)];
+    tokens.extend([
+        Token::Id(0x200a),
+        Token::Equal,
+        Token::Open,
+        Token::U32(1),
+        Token::Close,
+    ]);
+    tokens.extend(database(
+        0x2001,
+        &[(1, field(0x2006, b"ROM").to_vec()), (2, Vec::new())],
+    ));
+    tokens.extend(database(0x2002, &[(7, field(0x2007, b"roman").to_vec())]));
+    tokens.extend(database(
+        0x2003,
+        &[
+            (3, field(0x2008, b"citizen").to_vec()),
+            (4, field(0x2008, b"slaves").to_vec()),
+        ],
+    ));
+    tokens.extend(database(
+        0x2004,
+        &[(9, vec![Token::Id(0x2009), Token::Equal, Token::U32(1)])],
+    ));
+
+    let save = synthetic::binary_save(&tokens);
+    let file = ImperatorFile::from_slice(&save).unwrap();
+    let sections = GamestateSections::from_file(&file, &resolver).unwrap();
+    assert_eq!(
+        sections.keys().collect::<Vec<_>>(),
+        vec![
+            "played_country",
+            "country",
+            "character",
+            "population",
+            "provinces"
+        ]
+    );
+
+    let databases = (&file).deserialize_databases(&resolver).unwrap();
+    assert_eq!(databases.countries.database.len(), 1);
+    assert_eq!(databases.countries.database[&1].tag, "ROM");
+    assert_eq!(
+        databases.characters.database[&7].culture.as_deref(),
+        Some("roman")
+    );
+    assert_eq!(databases.pops.database.len(), 2);
+    assert_eq!(databases.pops.database[&4].kind.as_deref(), Some("slaves"));
+    assert_eq!(databases.provinces.database[&9].owner, Some(1));
+
+    // Text saves are indexed the same way
+    let mut melted = Vec::new();
+    (&file)
+        .melt(MeltOptions::new(), &resolver, &mut melted)
+        .unwrap();
+    let text = ImperatorFile::from_slice(&melted).unwrap();
+    assert_eq!((&text).deserialize_databases(&resolver).unwrap(), databases);
+}
+
+#[test]
+fn test_sections_skip_unknown_tokens() {
+    // Neither a value with a trailing container, a stray brace, nor a bare
+    // value stops the scan for the sections that follow
+    let gamestate = synthetic::GAMESTATE.replacen(
+        "\ncountry={",
+        "\ncolor=hsv { 0.5 0.25 0.75 }\n}\nunused\ncountry={",
+        1,
+    );
+    let expected = {
+        let file = ImperatorFile::from_slice(synthetic::text()).unwrap();
+        (&file).deserialize_databases(&*synthetic::TOKENS).unwrap()
+    };
+
+    for data in [
+        synthetic::text_with(&gamestate),
+        synthetic::binary_with(&gamestate),
+    ] {
+        let file = ImperatorFile::from_slice(&data).unwrap();
+        let sections = GamestateSections::from_file(&file, &*synthetic::TOKENS).unwrap();
+        assert_eq!(
+            sections.keys().collect::<Vec<_>>(),
+            vec![
+                "enabled_dlcs",
+                "game_rules",
+                "played_country",
+                "country",
+                "character",
+                "population",
+                "provinces"
+            ]
+        );
+        let databases = (&file).deserialize_databases(&*synthetic::TOKENS).unwrap();
+        assert_eq!(databases, expected);
+    }
+
+    // Splitting the binary gamestate for melting respects the same fields.
+    // The melter rejects stray braces, so only the other cases are melted.
+    let gamestate = gamestate.replacen("\n}\n", "\n", 1);
+    let binary = ImperatorFile::from_slice(synthetic::binary_with(&gamestate)).unwrap();
+    let mut sequential = Vec::new();
+    (&binary)
+        .melt(MeltOptions::new(), &*synthetic::TOKENS, &mut sequential)
+        .unwrap();
+    for threads in 1..=4 {
+        let mut parallel = Vec::new();
+        (&binary)
+            .melt_parallel(
+                MeltOptions::new(),
+                &*synthetic::TOKENS,
+                &mut parallel,
+                threads,
+            )
+            .unwrap();
+        assert_eq!(parallel, sequential);
+    }
+}
+
+#[test]
+fn test_deserialize_databases_missing_section() {
+    let gamestate = &synthetic::GAMESTATE[..synthetic::GAMESTATE.find("provinces=").unwrap()];
+    let file = ImperatorFile::from_slice(synthetic::text_with(gamestate)).unwrap();
+    let err = (&file)
+        .deserialize_databases(&*synthetic::TOKENS)
+        .unwrap_err();
+    assert!(
+        matches!(err.kind(), ImperatorErrorKind::MissingSection { name } if name == "provinces")
+    );
+}
+
+#[test]
+fn test_save_summary() {
+    let header = synthetic::header(SaveHeaderKind::UnifiedText, 0);
+    let meta = b"version=\"2.0.5\" date=460.3.12 ironman=yes meta_player_name=\"Rome\"\nenabled_dlcs={ \"Heirs of Alexander\" } play_time=120\n";
+    let gamestate = [&meta[..], &b"speed=3\n"[..].repeat(100)].concat();
+    let save = synthetic::zipped_save(header.clone(), meta, &[("gamestate", &gamestate)]);
+
+    let resolver: HashMap<u16, String> = HashMap::new();
+    let summary = SaveSummary::from_slice(&save, &resolver).unwrap();
+    assert_eq!(summary.version(), &GameVersion::new(2, 0, 5));
+    assert_eq!(summary.date().game_fmt().to_string(), "460.3.12");
+    assert_eq!(summary.country_name(), Some("Rome"));
+    assert!(summary.ironman());
+    assert_eq!(summary.dlcs(), &[String::from("Heirs of Alexander")]);
+    assert_eq!(summary.play_time(), 120);
+    assert_eq!(summary.kind(), SaveHeaderKind::UnifiedText);
+    assert_eq!(summary.file_size(), save.len() as u64);
+    assert_eq!(summary.entries().len(), 1);
+    assert_eq!(summary.entries()[0].name(), "gamestate");
+    assert_eq!(
+        summary.entries()[0].uncompressed_size(),
+        gamestate.len() as u64
+    );
+    assert!(summary.entries()[0].compressed_size() < gamestate.len() as u64);
+    assert_eq!(
+        summary.uncompressed_size(),
+        (header.header_len() + meta.len() + gamestate.len()) as u64
+    );
+
+    let file = ImperatorFile::from_slice(&save).unwrap();
+    let player = PlayerSummary::from_file(&file, &resolver).unwrap();
+    assert_eq!(player, PlayerSummary::default());
+
+    // Only the metadata is read, so a corrupt gamestate is not an error
+    let save = synthetic::zipped_save(header, meta, &[("gamestate", b"speed={{{")]);
+    let summary = SaveSummary::from_slice(&save, &resolver).unwrap();
+    assert_eq!(summary.country_name(), Some("Rome"));
+
+    // The player is resolved through the country database
+    for kind in [SaveHeaderKind::UnifiedText, SaveHeaderKind::SplitBinary] {
+        let save = synthetic::zip(kind);
+        let file = ImperatorFile::from_slice(&save).unwrap();
+        let player = PlayerSummary::from_file(&file, &*synthetic::TOKENS).unwrap();
+        assert_eq!(player.name(), Some("Gaius"));
+        assert_eq!(player.tag(), Some("ROM"));
+    }
+}
+
+#[test]
+fn test_metadata_fast_path() {
+    let header = synthetic::header(SaveHeaderKind::UnifiedText, 0);
+    let resolver: HashMap<u16, String> = HashMap::new();
+
+    // The gamestate is not valid, so it must not be touched
+    let meta = b"version=\"1.5.3\" date=450.10.1 enabled_dlcs={ } play_time=10\n";
+    let save = synthetic::zipped_save(header, meta, &[("gamestate", b"speed={{{")]);
+    let file = ImperatorFile::from_slice(&save).unwrap();
+    let metadata = file.metadata(&resolver).unwrap();
+    assert_eq!(metadata.version, GameVersion::new(1, 5, 3));
+    assert_eq!(metadata.play_time, 10);
+
+    // Debug saves do not declare a metadata region
+    let debug = synthetic::save(SaveHeaderKind::Text, 0, &[&meta[..], b"speed=3\n"].concat());
+    let file = ImperatorFile::from_slice(&debug).unwrap();
+    let metadata = file.metadata(&resolver).unwrap();
+    assert_eq!(metadata.date.game_fmt().to_string(), "450.10.1");
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_async_reader() {
+    use imperator_save::ImperatorAsyncReader;
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    /// Yields a few bytes at a time to exercise reads across boundaries
+    struct Trickle<'a>(&'a [u8]);
+
+    impl futures_util::io::AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            let len = buf.len().min(self.0.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    let resolver: HashMap<u16, String> = HashMap::new();
+    let meta = b"version=\"2.0.5\" date=460.3.12 enabled_dlcs={ } play_time=120\n";
+    let gamestate = [&meta[..], &b"speed=3\nplayers={ 1 2 3 }\n"[..].repeat(5000)].concat();
+    let header = synthetic::header(SaveHeaderKind::UnifiedText, 0);
+    let save = synthetic::zipped_save(header, meta, &[("gamestate", &gamestate)]);
+
+    let reader = futures_executor::block_on(ImperatorAsyncReader::new(Trickle(&save))).unwrap();
+    assert_eq!(reader.header().kind(), SaveHeaderKind::UnifiedText);
+    let metadata = reader.metadata(&resolver).unwrap().unwrap();
+    assert_eq!(metadata.play_time, 120);
+
+    let file = futures_executor::block_on(reader.into_file()).unwrap();
+    let mut actual = Vec::new();
+    (&file)
+        .melt(MeltOptions::new(), &resolver, &mut actual)
+        .unwrap();
+
+    let mut expected = Vec::new();
+    (&ImperatorFile::from_slice(&save).unwrap())
+        .melt(MeltOptions::new(), &resolver, &mut expected)
+        .unwrap();
+    assert_eq!(actual, expected);
+
+    // Split saves store the metadata as an entry
+    let header = synthetic::header(SaveHeaderKind::SplitText, 0);
+    let save = synthetic::zipped_save(header, b"", &[("meta", meta), ("gamestate", &gamestate)]);
+    let reader = futures_executor::block_on(ImperatorAsyncReader::new(&save[..])).unwrap();
+    let metadata = reader.metadata(&resolver).unwrap().unwrap();
+    assert_eq!(metadata.version, GameVersion::new(2, 0, 5));
+    let file = futures_executor::block_on(reader.into_file()).unwrap();
+    assert_eq!(file.header().metadata_len(), meta.len() as u64);
+
+    // The gamestate may precede the metadata entry
+    let header = synthetic::header(SaveHeaderKind::SplitText, 0);
+    let entries: [(&str, &[u8]); 3] = [
+        ("gamestate", &gamestate),
+        ("readme.txt", b"ignored"),
+        ("meta", meta),
+    ];
+    let save = synthetic::zipped_save(header, b"", &entries);
+    let reader = futures_executor::block_on(ImperatorAsyncReader::new(Trickle(&save))).unwrap();
+    let metadata = reader.metadata(&resolver).unwrap().unwrap();
+    assert_eq!(metadata.play_time, 120);
+    let file = futures_executor::block_on(reader.into_file()).unwrap();
+    assert_eq!(file.header().metadata_len(), meta.len() as u64);
+    let mut actual = Vec::new();
+    (&file)
+        .melt(MeltOptions::new(), &resolver, &mut actual)
+        .unwrap();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_metadata_version_shapes() {
+    // Older saves may omit fields that newer versions write, and both
+    // shapes go through the same model
+    let minimal = br#"version="Marius v1.4.2" date=450.10.1 enabled_dlcs={ } play_time=646"#;
+    let meta: Metadata = jomini::text::de::from_utf8_slice(minimal).unwrap();
+    assert_eq!(meta.version, GameVersion::new(1, 4, 2));
+    assert_eq!(meta.version.codename(), Some("Marius"));
+    assert!(!meta.ironman && !meta.iron);
+    assert_eq!(meta.meta_player_name, None);
+    assert!(meta.enabled_mods.is_empty());
+
+    let meta: Metadata = jomini::text::de::from_utf8_slice(synthetic::META.as_bytes()).unwrap();
+    assert_eq!(meta.version, GameVersion::new(2, 0, 5));
+    assert!(meta.version > GameVersion::new(1, 4, 2));
+    assert!(meta.ironman);
+    assert_eq!(meta.enabled_dlcs.len(), 2);
+
+    // Versions that can't be parsed are kept rather than failing
+    let future = br#"version="Ides v3" date=500.1.1 enabled_dlcs={ } play_time=0"#;
+    let meta: Metadata = jomini::text::de::from_utf8_slice(future).unwrap();
+    assert!(!meta.version.is_recognized());
+    assert_eq!(meta.version.raw(), "Ides v3");
+}
+
+#[test]
+fn test_synthetic_envelopes() {
+    let cases = [
+        (synthetic::text(), SaveHeaderKind::Text),
+        (synthetic::binary(), SaveHeaderKind::Binary),
+        (
+            synthetic::zip(SaveHeaderKind::UnifiedText),
+            SaveHeaderKind::UnifiedText,
+        ),
+        (
+            synthetic::zip(SaveHeaderKind::UnifiedBinary),
+            SaveHeaderKind::UnifiedBinary,
+        ),
+        (
+            synthetic::zip(SaveHeaderKind::SplitText),
+            SaveHeaderKind::SplitText,
+        ),
+        (
+            synthetic::zip(SaveHeaderKind::SplitBinary),
+            SaveHeaderKind::SplitBinary,
+        ),
+    ];
+
+    for (data, kind) in cases {
+        let file = ImperatorFile::from_slice(&data).unwrap();
+        assert_eq!(file.header().kind(), kind);
+        let zipped = matches!(file.kind(), JominiFileKind::Zip(_));
+        assert_eq!(
+            zipped,
+            !matches!(kind, SaveHeaderKind::Text | SaveHeaderKind::Binary)
+        );
+        (&file).validate(&*synthetic::TOKENS).unwrap();
+
+        let meta = file.metadata(&*synthetic::TOKENS).unwrap();
+        assert_eq!(meta.version, GameVersion::new(2, 0, 5));
+        assert_eq!(meta.date.game_fmt().to_string(), "460.3.12");
+        assert_eq!(meta.enabled_dlcs.len(), 2);
+    }
+}
+
+#[test]
+fn test_synthetic_deserialize() {
+    let saves = [
+        synthetic::text(),
+        synthetic::binary(),
+        synthetic::zip(SaveHeaderKind::UnifiedText),
+        synthetic::zip(SaveHeaderKind::UnifiedBinary),
+        synthetic::zip(SaveHeaderKind::SplitBinary),
+    ];
+
+    let expected = {
+        let file = ImperatorFile::from_slice(synthetic::text()).unwrap();
+        (&file).deserialize_databases(&*synthetic::TOKENS).unwrap()
+    };
+    assert_eq!(expected.countries.database.len(), 2);
+    assert_eq!(expected.countries.database[&1].currency_data.gold, 105.5);
+    assert_eq!(expected.pops.database.len(), 2);
+
+    for data in saves {
+        let file = ImperatorFile::from_slice(&data).unwrap();
+        let save: Save = (&file).deserialize(&*synthetic::TOKENS).unwrap();
+        assert_eq!(save.meta.version, GameVersion::new(2, 0, 5));
+        assert!(save.meta.ironman);
+        assert_eq!(save.meta.meta_player_name.as_deref(), Some("Rome"));
+        assert_eq!(save.meta.play_time, 3600);
+        assert_eq!(save.gamestate.speed, 3);
+        assert_eq!(
+            save.gamestate
+                .game_rules
+                .as_ref()
+                .and_then(|x| x.difficulty.as_deref()),
+            Some("hard")
+        );
+
+        let databases = (&file).deserialize_databases(&*synthetic::TOKENS).unwrap();
+        assert_eq!(databases, expected);
+    }
+}
+
+#[test]
+fn test_synthetic_melt() {
+    let options = MeltOptions::new().on_failed_resolve(FailedResolveStrategy::Error);
+    let binary = ImperatorFile::from_slice(synthetic::binary()).unwrap();
+    let mut melted = Vec::new();
+    let doc = (&binary)
+        .melt(options.clone(), &*synthetic::TOKENS, &mut melted)
+        .unwrap();
+    assert!(doc.unknown_tokens().is_empty());
+    assert_eq!(doc.dates_detected(), 2);
+
+    let file = ImperatorFile::from_slice(&melted).unwrap();
+    assert_eq!(file.header().kind(), SaveHeaderKind::Text);
+    let body = std::str::from_utf8(&melted[file.header().header_len()..]).unwrap();
+    assert!(body.starts_with("version=\"2.0.5\"\ndate=460.3.12\n"));
+    assert!(body.contains("birth_date=420.5.1"));
+    assert!(body.contains("gold=105.5"));
+
+    let meta = file.metadata(&*synthetic::TOKENS).unwrap();
+    assert_eq!(meta.meta_player_name.as_deref(), Some("Rome"));
+    let save: Save = (&file).deserialize(&*synthetic::TOKENS).unwrap();
+    assert_eq!(save.gamestate.speed, 3);
+
+    // The compressed save melts to the same output
+    let zip = ImperatorFile::from_slice(synthetic::zip(SaveHeaderKind::UnifiedBinary)).unwrap();
+    let mut zip_melted = Vec::new();
+    (&zip)
+        .melt(options, &*synthetic::TOKENS, &mut zip_melted)
+        .unwrap();
+    assert_eq!(zip_melted, melted);
+}
+
+#[test]
+fn test_melt_untrusted_metadata_len() {
+    // The declared metadata length must not size allocations up front
+    for mut data in [
+        synthetic::binary(),
+        synthetic::zip(SaveHeaderKind::UnifiedBinary),
+    ] {
+        data[15..23].copy_from_slice(b"ffffffff");
+        let file = ImperatorFile::from_slice(&data).unwrap();
+        assert_eq!(file.header().metadata_len(), u64::from(u32::MAX));
+        (&file)
+            .melt(MeltOptions::new(), &*synthetic::TOKENS, std::io::sink())
+            .unwrap();
+        (&file)
+            .melt_parallel(MeltOptions::new(), &*synthetic::TOKENS, std::io::sink(), 2)
+            .unwrap();
+    }
+}
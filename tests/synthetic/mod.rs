@@ -0,0 +1,194 @@
+//! Small saves generated from a text document and a fake token table so that
+//! melting, deserialization and the envelope can be tested without
+//! downloading sample saves or having access to the real tokens.
+
+use imperator_save::{ImperatorDate, SaveHeader, SaveHeaderKind, TokenTable};
+use jomini::{
+    binary::Token,
+    text::{self, Operator},
+    Scalar,
+};
+use std::{collections::HashMap, io::Write, sync::LazyLock};
+
+/// Tokens for every key in the synthetic save
+pub static TOKENS: LazyLock<TokenTable> = LazyLock::new(|| {
+    TokenTable::from_slice(include_bytes!("../fixtures/synthetic-tokens.txt")).unwrap()
+});
+
+/// The metadata, which the gamestate begins with
+pub const META: &str = r#"version="2.0.5"
+date=460.3.12
+ironman=yes
+meta_player_name="Rome"
+enabled_dlcs={ "Heirs of Alexander" "Magna Graecia" }
+play_time=3600
+"#;
+
+/// The gamestate following the metadata
+pub const GAMESTATE: &str = r#"speed=3
+game_rules={ difficulty=hard }
+played_country={ player_name="Gaius" country=1 }
+country={
+	database={
+		1={ tag="ROM" currency_data={ gold=105.5 manpower=3.25 stability=50 } }
+		2=none
+		3={ tag="CAR" }
+	}
+}
+character={
+	database={
+		7={ country=1 culture=roman religion=roman_pantheon birth_date=420.5.1 }
+	}
+}
+population={
+	database={
+		11={ type=citizen culture=roman }
+		12={ type=slaves culture=carthaginian }
+	}
+}
+provinces={
+	database={
+		1={ owner=1 culture=roman }
+	}
+}
+"#;
+
+/// A header of the given kind based on a real save's header
+pub fn header(kind: SaveHeaderKind, metadata_len: usize) -> SaveHeader {
+    let data = include_bytes!("../fixtures/header");
+    let mut header = SaveHeader::from_slice(&data[..]).unwrap();
+    header.set_kind(kind);
+    header.set_metadata_len(metadata_len as u64);
+    header
+}
+
+/// Encode a text document in the binary format using [`TOKENS`]. Scalars
+/// that parse as a date, integer, or decimal are written as such.
+pub fn encode(data: &str) -> Vec<u8> {
+    let ids: HashMap<&str, u16> = TOKENS.iter().map(|(id, token)| (token, id)).collect();
+    let mut out = Vec::new();
+    let mut reader = text::TokenReader::from_slice(data.as_bytes());
+    while let Some(token) = reader.next().unwrap() {
+        let token = match token {
+            text::Token::Open => Token::Open,
+            text::Token::Close => Token::Close,
+            text::Token::Operator(Operator::Equal) => Token::Equal,
+            text::Token::Operator(x) => panic!("unsupported operator: {:?}", x),
+            text::Token::Quoted(x) => Token::Quoted(x),
+            text::Token::Unquoted(x) => {
+                let s = std::str::from_utf8(x.as_bytes()).unwrap();
+                if let Some(&id) = ids.get(s) {
+                    Token::Id(id)
+                } else if s == "yes" || s == "no" {
+                    Token::Bool(s == "yes")
+                } else if let Ok(x) = s.parse::<i32>() {
+                    Token::I32(x)
+                } else if let Ok(date) = ImperatorDate::parse(s) {
+                    Token::I32(date.to_binary())
+                } else if let Ok(x) = s.parse::<f64>() {
+                    Token::F64(((x * 100000.0).round() as i64).to_le_bytes())
+                } else {
+                    Token::Unquoted(Scalar::new(x.as_bytes()))
+                }
+            }
+        };
+        token.write(&mut out).unwrap();
+    }
+    out
+}
+
+/// An uncompressed text save
+pub fn text() -> Vec<u8> {
+    text_with(GAMESTATE)
+}
+
+/// An uncompressed binary save
+pub fn binary() -> Vec<u8> {
+    binary_with(GAMESTATE)
+}
+
+/// An uncompressed text save with [`META`] followed by the given gamestate
+pub fn text_with(gamestate: &str) -> Vec<u8> {
+    let body = format!("{}{}", META, gamestate);
+    save(SaveHeaderKind::Text, META.len(), body.as_bytes())
+}
+
+/// An uncompressed binary save with [`META`] followed by the given gamestate
+pub fn binary_with(gamestate: &str) -> Vec<u8> {
+    let mut body = encode(META);
+    let meta_len = body.len();
+    body.extend_from_slice(&encode(gamestate));
+    save(SaveHeaderKind::Binary, meta_len, &body)
+}
+
+/// An uncompressed save of the given kind whose body starts with
+/// `metadata_len` bytes of metadata
+pub fn save(kind: SaveHeaderKind, metadata_len: usize, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    header(kind, metadata_len).write(&mut out).unwrap();
+    out.extend_from_slice(body);
+    out
+}
+
+/// An uncompressed binary save of the tokens without a metadata section
+pub fn binary_save(tokens: &[Token]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for token in tokens {
+        token.write(&mut body).unwrap();
+    }
+    save(SaveHeaderKind::Binary, 0, &body)
+}
+
+/// A compressed save of the given kind. Unified saves inline the metadata
+/// before the zip while split saves store it as an entry.
+pub fn zip(kind: SaveHeaderKind) -> Vec<u8> {
+    zip_with(kind, GAMESTATE)
+}
+
+/// A compressed save of the given kind with [`META`] followed by the given
+/// gamestate
+pub fn zip_with(kind: SaveHeaderKind, gamestate: &str) -> Vec<u8> {
+    let gamestate = format!("{}{}", META, gamestate);
+    let (meta, gamestate) = if kind.is_binary() {
+        (encode(META), encode(&gamestate))
+    } else {
+        (META.as_bytes().to_vec(), gamestate.into_bytes())
+    };
+
+    let header = header(kind, 0);
+    match kind {
+        SaveHeaderKind::UnifiedText | SaveHeaderKind::UnifiedBinary => {
+            zipped_save(header, &meta, &[("gamestate", &gamestate)])
+        }
+        SaveHeaderKind::SplitText | SaveHeaderKind::SplitBinary => {
+            zipped_save(header, b"", &[("meta", &meta), ("gamestate", &gamestate)])
+        }
+        _ => panic!("{:?} is not a compressed save kind", kind),
+    }
+}
+
+/// Write a save with the metadata inlined before a zip of the given entries
+pub fn zipped_save(mut header: SaveHeader, meta: &[u8], entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    header.set_metadata_len(meta.len() as u64);
+    header.write(&mut out).unwrap();
+    out.extend_from_slice(meta);
+    let mut archive = rawzip::ZipArchiveWriter::builder()
+        .with_offset(out.len() as u64)
+        .build(&mut out);
+    for (name, data) in entries {
+        let (mut entry, config) = archive
+            .new_file(name)
+            .compression_method(rawzip::CompressionMethod::Deflate)
+            .start()
+            .unwrap();
+        let encoder = flate2::write::DeflateEncoder::new(&mut entry, flate2::Compression::fast());
+        let mut writer = config.wrap(encoder);
+        writer.write_all(data).unwrap();
+        let (encoder, descriptor) = writer.finish().unwrap();
+        encoder.finish().unwrap();
+        entry.finish(descriptor).unwrap();
+    }
+    archive.finish().unwrap();
+    out
+}
@@ -13,6 +13,12 @@ static DATA: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
 /// repository of saves.
 pub fn request_file<S: AsRef<str>>(input: S) -> File {
     let reffed = input.as_ref();
+    try_request_file(reffed).unwrap_or_else(|e| panic!("unable to fetch {}: {}", reffed, e))
+}
+
+/// Like [`request_file`] but returns an error instead of panicking when the
+/// save can't be fetched (eg: when offline)
+pub fn try_request_file(reffed: &str) -> Result<File, Box<dyn std::error::Error>> {
     let cache = Path::new("assets").join("saves").join(reffed);
     if cache.exists() {
         println!("cache hit: {}", reffed);
@@ -24,17 +30,19 @@ pub fn request_file<S: AsRef<str>>(input: S) -> File {
         } else {
             println!("cache miss: {}", reffed);
             let url = format!("https://cdn-dev.pdx.tools/imperator-saves/{}", reffed);
-            let mut resp = attohttpc::get(&url).send().unwrap();
+            let mut resp = attohttpc::get(&url).send()?;
 
             if !resp.is_success() {
-                panic!("expected a 200 code from s3");
+                return Err(
+                    format!("expected a 200 code from s3 but received {}", resp.status()).into(),
+                );
             } else {
-                std::fs::create_dir_all(cache.parent().unwrap()).unwrap();
-                let mut f = std::fs::File::create(&cache).unwrap();
-                std::io::copy(&mut resp, &mut f).unwrap();
+                std::fs::create_dir_all(cache.parent().unwrap())?;
+                let mut f = std::fs::File::create(&cache)?;
+                std::io::copy(&mut resp, &mut f)?;
             }
         }
     }
 
-    std::fs::File::open(cache).unwrap()
+    Ok(std::fs::File::open(cache)?)
 }